@@ -0,0 +1,95 @@
+use js_sys::Date;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use crate::{APP_DATA, AppData, get_element_by_id, modified};
+use crate::review::{Review, Status};
+
+//保存审核人名字的localStorage键
+const REVIEWER_KEY: &str = "reviewer";
+
+fn status_select() -> HtmlSelectElement{
+    get_element_by_id("review_status").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+fn reviewer_input() -> HtmlInputElement{
+    get_element_by_id("reviewer").dyn_into::<HtmlInputElement>().unwrap()
+}
+
+fn notes_input() -> HtmlTextAreaElement{
+    get_element_by_id("review_notes").dyn_into::<HtmlTextAreaElement>().unwrap()
+}
+
+fn storage() -> Option<web_sys::Storage>{
+    web_sys::window().unwrap().local_storage().ok().flatten()
+}
+
+//添加审核状态的选项
+pub fn fill_status(app_data:&AppData, select:&HtmlSelectElement) -> Result<(), JsValue>{
+    for status in Status::ALL.iter(){
+        let option = app_data.document.create_element("option")?;
+        option.set_attribute("value", status.id())?;
+        option.set_text_content(Some(status.name()));
+        select.append_child(&option)?;
+    }
+    Ok(())
+}
+
+pub fn bind(app_data:&AppData) -> Result<JsValue, JsValue>{
+    fill_status(app_data, &status_select())?;
+    if let Some(reviewer) = storage().and_then(|storage| storage.get_item(REVIEWER_KEY).ok().flatten()){
+        reviewer_input().set_value(&reviewer);
+    }
+
+    //修改状态或者备注后记录审核人和时间
+    let on_review_change = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            update(&mut app_data)
+        }).expect("保存审核信息失败");
+    }) as Box<dyn FnMut()>);
+    status_select().set_onchange(Some(on_review_change.as_ref().unchecked_ref()));
+    notes_input().set_onchange(Some(on_review_change.as_ref().unchecked_ref()));
+    on_review_change.forget();
+
+    let on_reviewer_change = Closure::wrap(Box::new(move || {
+        if let Some(storage) = storage(){
+            storage.set_item(REVIEWER_KEY, reviewer_input().value().trim()).expect("保存审核人失败");
+        }
+    }) as Box<dyn FnMut()>);
+    reviewer_input().set_onchange(Some(on_reviewer_change.as_ref().unchecked_ref()));
+    on_reviewer_change.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+fn update(app_data:&mut AppData) -> Result<JsValue, JsValue>{
+    let ch = match app_data.select.value().chars().next(){
+        Some(ch) => ch,
+        None => return Ok(JsValue::FALSE)
+    };
+    let review = Review{
+        status: Status::from_id(&status_select().value()).unwrap_or_default(),
+        reviewer: reviewer_input().value().trim().to_string(),
+        time: Date::now(),
+        notes: notes_input().value(),
+    };
+    app_data.reviews.insert(ch, review);
+    modified(app_data, ch);
+    show(app_data, ch);
+    Ok(JsValue::TRUE)
+}
+
+//显示当前字的审核信息
+pub fn show(app_data:&AppData, ch: char){
+    let review = app_data.reviews.get(&ch).cloned().unwrap_or_default();
+    status_select().set_value(review.status.id());
+    notes_input().set_value(&review.notes);
+    let info = if review.time > 0.0{
+        let time = Date::new(&JsValue::from_f64(review.time)).to_locale_string("zh-CN", &JsValue::UNDEFINED);
+        format!("{} {}", review.reviewer, String::from(time))
+    }else{
+        String::new()
+    };
+    get_element_by_id("review_info").set_inner_text(&info);
+}
@@ -0,0 +1,358 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use bincode::serialize;
+use js_sys::{Array, Date, Function, Object, Reflect, Uint16Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::{APP_DATA, annotation, current_list, delete_char, draw_ch, fill_chars, log, modified, move_char, patch_data, save, select_char, strokes_data};
+use crate::data::{MAX_COORD, Strokes};
+use crate::matching;
+use crate::patch::{Patch, write_patch};
+use crate::recognize::Recognizer;
+use crate::review::{self, Review, Status};
+
+//给前端和浏览器控制台使用的接口(加载页面后通过import('/rust_stroke_editor.js')得到的模块调用)
+//一笔用平铺的坐标数组[x0, y0, x1, y1, ...]表示, 一个字是这样的数组的数组
+//修改数据的接口和页面上的操作一样会更新字符列表、自动保存并触发onChange
+
+thread_local!{
+    //识别用的数据, 数据被修改时清除
+    static RECOGNIZER: RefCell<Option<Rc<Recognizer>>> = const { RefCell::new(None) };
+    //onChange注册的回调: (编号, 回调)
+    static LISTENERS: RefCell<Vec<(u32, Function)>> = const { RefCell::new(vec![]) };
+    static NEXT_ID: Cell<u32> = const { Cell::new(1) };
+}
+
+//两笔的相似度
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct StrokeSimilarity{
+    //按长度均匀采样后对应点的平均距离(字形坐标)
+    pub distance: f64,
+    //归一化之后的平均DTW距离(只比较形状, 和位置、大小无关)
+    pub dtw: f64,
+    //归一化之后的离散Fréchet距离
+    pub frechet: f64,
+    //起点到终点方向的余弦(-1~1)
+    pub direction: f64,
+    #[wasm_bindgen(js_name = startError)]
+    pub start_error: f64,
+    #[wasm_bindgen(js_name = endError)]
+    pub end_error: f64,
+    //练习模式中是否算写对
+    pub accepted: bool,
+}
+
+//[x0, y0, x1, y1, ...] -> [(x0, y0), (x1, y1), ...]
+pub fn parse_stroke(flat:&[u16]) -> Result<Vec<(u16, u16)>, JsValue>{
+    if flat.is_empty() || !flat.len().is_multiple_of(2){
+        return Err(JsValue::from_str("坐标个数必须是不为0的偶数"));
+    }
+    Ok(flat.chunks(2).map(|point| (point[0], point[1])).collect())
+}
+
+//比较用户写的一笔和数据中的一笔
+#[wasm_bindgen(js_name = compareStrokes)]
+pub fn compare_strokes(drawn:&[u16], stored:&[u16]) -> Result<StrokeSimilarity, JsValue>{
+    let result = matching::compare(&parse_stroke(drawn)?, &parse_stroke(stored)?);
+    Ok(StrokeSimilarity{
+        distance: result.distance,
+        dtw: result.dtw,
+        frechet: result.frechet,
+        direction: result.direction,
+        start_error: result.start_error,
+        end_error: result.end_error,
+        accepted: result.accepted(),
+    })
+}
+
+//识别出的一个候选字
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct RecognizedChar{
+    ch: char,
+    //平均每一笔的距离(越小越像)
+    pub score: f64,
+    //笔顺错的对数
+    #[wasm_bindgen(js_name = orderErrors)]
+    pub order_errors: usize,
+}
+
+#[wasm_bindgen]
+impl RecognizedChar{
+    #[wasm_bindgen(getter, js_name = char)]
+    pub fn ch(&self) -> String{
+        self.ch.to_string()
+    }
+}
+
+//JS数组中的每一笔(数组或者Uint16Array)
+fn parse_strokes(strokes:&Array) -> Result<Strokes, JsValue>{
+    strokes.iter().map(|stroke| parse_stroke(&Uint16Array::new(&stroke).to_vec())).collect()
+}
+
+//在当前数据中查找和写的字最像的limit个字, 允许笔顺错误
+#[wasm_bindgen]
+pub fn recognize(strokes: Array, limit: usize) -> Result<Array, JsValue>{
+    let drawn = parse_strokes(&strokes)?;
+    let recognizer = match RECOGNIZER.with(|recognizer| recognizer.borrow().clone()){
+        Some(recognizer) => recognizer,
+        None => {
+            let recognizer = APP_DATA.with(|app_data| {
+                let app_data = app_data.borrow();
+                Rc::new(Recognizer::new(app_data.strokes.iter().map(|(ch, strokes)| (*ch, strokes))))
+            });
+            RECOGNIZER.with(|cache| *cache.borrow_mut() = Some(recognizer.clone()));
+            recognizer
+        }
+    };
+    let candidates = recognizer.recognize(&drawn, limit);
+    Ok(candidates.into_iter().map(|candidate| JsValue::from(RecognizedChar{
+        ch: candidate.ch,
+        score: candidate.score,
+        order_errors: candidate.order_errors,
+    })).collect())
+}
+
+fn to_char(text:&str) -> Result<char, JsValue>{
+    text.chars().next().ok_or_else(|| JsValue::from_str("字符不能为空"))
+}
+
+fn strokes_to_js(strokes:&Strokes) -> Array{
+    strokes.iter().map(|stroke| {
+        let flat: Array = stroke.iter().flat_map(|(x, y)| [*x, *y]).map(JsValue::from).collect();
+        JsValue::from(flat)
+    }).collect()
+}
+
+//当前选择的字
+fn selected() -> Option<char>{
+    APP_DATA.with(|app_data| app_data.borrow().select.value().chars().next())
+}
+
+//通知数据被修改了: kind是"modified"(新增或者修改)、"removed"或者"reload"(整个数据被替换, 没有char)
+//回调在当前的操作结束之后调用, 回调中可以继续调用这里的接口
+//识别用的数据也在这里清除, 下次识别时重新生成
+pub fn notify(kind:&'static str, ch: Option<char>){
+    RECOGNIZER.with(|recognizer| *recognizer.borrow_mut() = None);
+    let listeners: Vec<Function> = LISTENERS.with(|listeners| listeners.borrow().iter().map(|(_, callback)| callback.clone()).collect());
+    if listeners.is_empty(){
+        return;
+    }
+    spawn_local(async move{
+        let event = Object::new();
+        let _ = Reflect::set(&event, &JsValue::from_str("type"), &JsValue::from_str(kind));
+        if let Some(ch) = ch{
+            let _ = Reflect::set(&event, &JsValue::from_str("char"), &JsValue::from_str(&ch.to_string()));
+        }
+        for callback in listeners{
+            if let Err(err) = callback.call1(&JsValue::NULL, &event){
+                log(&format!("onChange回调出错:{:?}", err));
+            }
+        }
+    });
+}
+
+//注册修改的回调, 返回用来取消的编号
+#[wasm_bindgen(js_name = onChange)]
+pub fn on_change(callback: Function) -> u32{
+    let id = NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    LISTENERS.with(|listeners| listeners.borrow_mut().push((id, callback)));
+    id
+}
+
+#[wasm_bindgen(js_name = offChange)]
+pub fn off_change(id: u32) -> bool{
+    LISTENERS.with(|listeners| {
+        let mut listeners = listeners.borrow_mut();
+        let count = listeners.len();
+        listeners.retain(|(i, _)| *i != id);
+        listeners.len() != count
+    })
+}
+
+//按字符顺序排列的所有字
+#[wasm_bindgen(js_name = getChars)]
+pub fn get_chars() -> Array{
+    APP_DATA.with(|app_data| app_data.borrow().chars.iter().map(|ch| JsValue::from_str(&ch.to_string())).collect())
+}
+
+#[wasm_bindgen(js_name = getSelectedChar)]
+pub fn get_selected_char() -> Option<String>{
+    selected().map(|ch| ch.to_string())
+}
+
+//在字符列表中选择并显示一个字, 不存在时返回false
+#[wasm_bindgen(js_name = selectChar)]
+pub fn select(ch:&str) -> Result<bool, JsValue>{
+    let ch = to_char(ch)?;
+    APP_DATA.with(|app_data| -> Result<bool, JsValue>{
+        let app_data = app_data.borrow();
+        if !app_data.strokes.contains_key(&ch){
+            return Ok(false);
+        }
+        select_char(&app_data, ch)?;
+        draw_ch(&app_data, ch.to_string(), true, true)?;
+        Ok(true)
+    })
+}
+
+//一个字的笔画, 不存在时返回undefined
+#[wasm_bindgen(js_name = getStrokes)]
+pub fn get_strokes(ch:&str) -> Result<Option<Array>, JsValue>{
+    let ch = to_char(ch)?;
+    Ok(APP_DATA.with(|app_data| app_data.borrow().strokes.get(&ch).map(strokes_to_js)))
+}
+
+//设置一个字的笔画, 不存在时添加这个字
+#[wasm_bindgen(js_name = setStrokes)]
+pub fn set_strokes(ch:&str, strokes: Array) -> Result<(), JsValue>{
+    let ch = to_char(ch)?;
+    let strokes = parse_strokes(&strokes)?;
+    if strokes.is_empty(){
+        return Err(JsValue::from_str("笔画不能为空"));
+    }
+    if let Some((x, y)) = strokes.iter().flatten().find(|(x, y)| *x > MAX_COORD || *y > MAX_COORD){
+        return Err(JsValue::from_str(&format!("点({},{})超出范围", x, y)));
+    }
+    let current = selected();
+    APP_DATA.with(|app_data| -> Result<(), JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let added = app_data.chars.insert(ch).is_some();
+        app_data.strokes.insert(ch, strokes);
+        if added{
+            fill_chars(&app_data)?;
+            if let Some(current) = current{
+                select_char(&app_data, current)?;
+            }
+        }
+        modified(&app_data, ch);
+        if current.is_none() || current == Some(ch){
+            draw_ch(&app_data, app_data.select.value(), true, true)?;
+        }
+        Ok(())
+    })
+}
+
+//删除一个字, 不存在时返回false
+#[wasm_bindgen(js_name = removeChar)]
+pub fn remove_char(ch:&str) -> Result<bool, JsValue>{
+    let ch = to_char(ch)?;
+    let current = selected();
+    APP_DATA.with(|app_data| -> Result<bool, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let index = app_data.select.selected_index();
+        if !delete_char(&mut app_data, ch){
+            return Ok(false);
+        }
+        fill_chars(&app_data)?;
+        match current.filter(|current| *current != ch){
+            Some(current) => select_char(&app_data, current)?,
+            None if app_data.select.length() > 0 => app_data.select.set_selected_index(index.min(app_data.select.length() as i32 - 1)),
+            None => ()
+        }
+        draw_ch(&app_data, app_data.select.value(), true, true)?;
+        Ok(true)
+    })
+}
+
+//把from改为to(笔画、笔画宽度和审核信息一起移动)
+#[wasm_bindgen(js_name = renameChar)]
+pub fn rename_char(from:&str, to:&str) -> Result<(), JsValue>{
+    let (from, to) = (to_char(from)?, to_char(to)?);
+    if from == to{
+        return Ok(());
+    }
+    let current = selected();
+    APP_DATA.with(|app_data| -> Result<(), JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        move_char(&mut app_data, from, to).map_err(|err| JsValue::from_str(&err))?;
+        fill_chars(&app_data)?;
+        let current = if current == Some(from) { to } else { current.unwrap_or(to) };
+        select_char(&app_data, current)?;
+        modified(&app_data, to);
+        draw_ch(&app_data, current.to_string(), true, true)?;
+        Ok(())
+    })
+}
+
+//审核信息{status, reviewer, time, notes}, 没有审核信息时返回undefined
+#[wasm_bindgen(js_name = getReview)]
+pub fn get_review(ch:&str) -> Result<Option<Object>, JsValue>{
+    let ch = to_char(ch)?;
+    let review = APP_DATA.with(|app_data| app_data.borrow().reviews.get(&ch).cloned());
+    let review = match review{
+        Some(review) => review,
+        None => return Ok(None)
+    };
+    let object = Object::new();
+    Reflect::set(&object, &JsValue::from_str("status"), &JsValue::from_str(review.status.id()))?;
+    Reflect::set(&object, &JsValue::from_str("reviewer"), &JsValue::from_str(&review.reviewer))?;
+    Reflect::set(&object, &JsValue::from_str("time"), &JsValue::from_f64(review.time))?;
+    Reflect::set(&object, &JsValue::from_str("notes"), &JsValue::from_str(&review.notes))?;
+    Ok(Some(object))
+}
+
+//设置审核信息, status是"unreviewed"、"verified"或者"needs_work", 时间为当前时间
+#[wasm_bindgen(js_name = setReview)]
+pub fn set_review(ch:&str, status:&str, reviewer:&str, notes:&str) -> Result<(), JsValue>{
+    let ch = to_char(ch)?;
+    let status = Status::from_id(status).ok_or_else(|| JsValue::from_str(&format!("未知的审核状态:{}", status)))?;
+    let current = selected();
+    APP_DATA.with(|app_data| -> Result<(), JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        if !app_data.strokes.contains_key(&ch){
+            return Err(JsValue::from_str(&format!("字符不存在:{}", ch)));
+        }
+        app_data.reviews.insert(ch, Review{
+            status,
+            reviewer: reviewer.to_string(),
+            time: Date::now(),
+            notes: notes.to_string(),
+        });
+        modified(&app_data, ch);
+        if current == Some(ch){
+            annotation::show(&app_data, ch);
+        }
+        Ok(())
+    })
+}
+
+//应用STROKES.patch的内容, 返回{added, modified, removed}, 格式错误或者有冲突时抛出异常(数据不会被修改)
+#[wasm_bindgen(js_name = applyPatch)]
+pub fn apply_patch_data(data: Vec<u8>) -> Result<Object, JsValue>{
+    let (added, modified, removed) = patch_data(&data)?;
+    let object = Object::new();
+    Reflect::set(&object, &JsValue::from_str("added"), &JsValue::from_f64(added as f64))?;
+    Reflect::set(&object, &JsValue::from_str("modified"), &JsValue::from_f64(modified as f64))?;
+    Reflect::set(&object, &JsValue::from_str("removed"), &JsValue::from_f64(removed as f64))?;
+    Ok(object)
+}
+
+//导出当前数据: "vec"(STROKES.data)、"map"(HashMap格式)、"patch"(STROKES.patch)或者"json"
+#[wasm_bindgen(js_name = exportData)]
+pub fn export_data(format:&str) -> Result<Vec<u8>, JsValue>{
+    APP_DATA.with(|app_data| -> Result<Vec<u8>, JsValue>{
+        let app_data = app_data.borrow();
+        match format{
+            "vec" => Ok(strokes_data(&app_data)),
+            "map" => serialize(&app_data.strokes).map_err(|err| JsValue::from_str(&err.to_string())),
+            "patch" => {
+                let patch = Patch::diff(&app_data.base, &app_data.strokes, &app_data.chars);
+                Ok(write_patch(&patch, &review::changed(&app_data.base_reviews, &app_data.reviews), &app_data.variants.added(&app_data.base_variants)))
+            }
+            "json" => serde_json::to_vec(&current_list(&app_data)).map_err(|err| JsValue::from_str(&err.to_string())),
+            _ => Err(JsValue::from_str(&format!("未知的格式:{}", format)))
+        }
+    })
+}
+
+//和点击"保存"相同
+#[wasm_bindgen(js_name = save)]
+pub fn save_data() -> Result<JsValue, JsValue>{
+    save(false)
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use js_sys::{Date, Object, Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::BeforeUnloadEvent;
+use bincode::serialize;
+use crate::{APP_DATA, get_element_by_id, log, strokes_data};
+use crate::dataset::current_name;
+use crate::idb;
+
+//IndexedDB中自动保存的记录, 每个数据文件一条
+const AUTOSAVE_KEY: &str = "autosave";
+//最后一次修改之后多久自动保存(毫秒)
+const AUTOSAVE_DELAY: i32 = 1000;
+
+#[derive(Default)]
+struct State{
+    //有未保存的修改的数据
+    modified: HashSet<String>,
+    //等待中的自动保存
+    timer: Option<i32>,
+    //每个数据上次自动保存的时间
+    saved_at: HashMap<String, f64>,
+}
+
+thread_local!{
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+//有未保存的修改时离开页面需要确认
+pub fn bind(){
+    let on_before_unload = Closure::wrap(Box::new(move |event: BeforeUnloadEvent| {
+        if STATE.with(|state| !state.borrow().modified.is_empty()){
+            event.prevent_default();
+            event.set_return_value("确认要离开此页面吗？");
+        }
+    }) as Box<dyn FnMut(_)>);
+    web_sys::window().unwrap().set_onbeforeunload(Some(on_before_unload.as_ref().unchecked_ref()));
+    on_before_unload.forget();
+}
+
+//数据被修改, 稍后自动保存
+pub fn modified(name:&str){
+    let window = web_sys::window().unwrap();
+    let timer = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.modified.insert(name.to_string());
+        state.timer.take()
+    });
+    if let Some(timer) = timer{
+        window.clear_timeout_with_handle(timer);
+    }
+    let on_timeout = Closure::once_into_js(move || {
+        STATE.with(|state| state.borrow_mut().timer = None);
+        save();
+    });
+    let timer = window.set_timeout_with_callback_and_timeout_and_arguments_0(on_timeout.unchecked_ref(), AUTOSAVE_DELAY).ok();
+    STATE.with(|state| state.borrow_mut().timer = timer);
+    show_status(name);
+}
+
+//切换数据之前立即保存等待中的编辑
+pub fn flush(){
+    if let Some(timer) = STATE.with(|state| state.borrow_mut().timer.take()){
+        web_sys::window().unwrap().clear_timeout_with_handle(timer);
+        save();
+    }
+}
+
+//数据已经保存到磁盘上的文件或者服务器(只生成下载链接不算保存)
+pub fn saved(name:&str){
+    STATE.with(|state| state.borrow_mut().modified.remove(name));
+    show_status(name);
+}
+
+//显示当前编辑的数据的状态, 切换数据之后也需要调用
+pub fn show_status(name:&str){
+    let text = STATE.with(|state| {
+        let state = state.borrow();
+        match (state.modified.contains(name), state.saved_at.get(name)){
+            (false, _) => String::new(),
+            (true, None) => "有未保存的修改".to_string(),
+            (true, Some(time)) => {
+                let time = Date::new(&JsValue::from_f64(*time)).to_locale_time_string("zh-CN");
+                format!("有未保存的修改(已自动保存于{})", String::from(time))
+            }
+        }
+    });
+    get_element_by_id("status").set_inner_text(&text);
+}
+
+//数据文件对应的记录, 默认的STROKES.data沿用原来的记录
+fn key(name:&str) -> String{
+    if name == "STROKES.data"{
+        AUTOSAVE_KEY.to_string()
+    }else{
+        format!("{}:{}", AUTOSAVE_KEY, name)
+    }
+}
+
+//立即读取当前的数据, 之后再写入IndexedDB(切换数据之后也不会保存错)
+fn save(){
+    let (name, strokes, widths) = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        (current_name(&app_data), strokes_data(&app_data), serialize(&app_data.widths).unwrap())
+    });
+    spawn_local(async move{
+        if let Err(err) = write(&name, strokes, widths).await{
+            log(&format!("自动保存失败:{:?}", err));
+        }
+    });
+}
+
+async fn write(name:&str, strokes: Vec<u8>, widths: Vec<u8>) -> Result<(), JsValue>{
+    let time = Date::now();
+    let record = Object::new();
+    Reflect::set(&record, &"time".into(), &JsValue::from_f64(time))?;
+    Reflect::set(&record, &"strokes".into(), &Uint8Array::from(&strokes[..]))?;
+    Reflect::set(&record, &"widths".into(), &Uint8Array::from(&widths[..]))?;
+    idb::put(&key(name), &record).await?;
+    STATE.with(|state| state.borrow_mut().saved_at.insert(name.to_string(), time));
+    //保存的可能是切换之前的数据
+    let current = APP_DATA.with(|app_data| current_name(&app_data.borrow()));
+    show_status(&current);
+    Ok(())
+}
+
+//加载数据时检查有没有自动保存的编辑, 询问是否恢复
+//恢复时返回自动保存的(笔画数据, 宽度数据), 不恢复时删除自动保存的记录
+pub async fn restore(name:&str, data:&[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, JsValue>{
+    let record = idb::get(&key(name)).await?;
+    if record.is_undefined(){
+        return Ok(None);
+    }
+    let strokes = Uint8Array::new(&Reflect::get(&record, &"strokes".into())?).to_vec();
+    let widths = Uint8Array::new(&Reflect::get(&record, &"widths".into())?).to_vec();
+    //和加载的数据相同, 不需要恢复
+    if strokes == data{
+        return Ok(None);
+    }
+    let time = Reflect::get(&record, &"time".into())?;
+    let time = String::from(Date::new(&time).to_locale_string("zh-CN", &JsValue::UNDEFINED));
+    let window = web_sys::window().unwrap();
+    if window.confirm_with_message(&format!("发现{}自动保存的{}的未保存编辑, 是否恢复?", time, name))?{
+        Ok(Some((strokes, widths)))
+    }else{
+        idb::delete(&key(name)).await?;
+        Ok(None)
+    }
+}
@@ -0,0 +1,201 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlSelectElement};
+use crate::{APP_DATA, AppData, alert, api, autosave, bind_open_file, download, current_list, draw_ch, draw_reference, fetch_data, fill_chars, get_element_by_id, log, select_char};
+use crate::data::{Strokes, list_size, read_any};
+use crate::dataset::{current_name, current_script};
+use crate::diff::diff;
+use crate::merge::merge;
+use crate::review::{merge_reviews, read_reviews, reviews_size};
+use crate::variant::read_variants;
+use crate::view::GLYPH_SIZE;
+
+//点移动不超过这个距离(字形坐标)时认为没有变化
+const TOLERANCE: f64 = 5.0;
+//旧版本的数据(html目录中)
+const OLD_DATA: &str = "OLD_STROKE.map";
+
+pub fn bind() -> Result<JsValue, JsValue>{
+    bind_open_file("btn_compare", "compare_file", open_compare);
+    bind_open_file("btn_merge", "merge_file", open_merge);
+
+    //和旧版本的数据对比
+    let btn_compare_old_click = Closure::wrap(Box::new(move || {
+        wasm_bindgen_futures::spawn_local(async{
+            match fetch_data(OLD_DATA).await{
+                Ok(data) => {
+                    open_compare(data).expect("对比失败");
+                }
+                Err(err) => {
+                    log(&format!("{:?}", err));
+                    alert(&format!("没有找到{}!", OLD_DATA));
+                }
+            }
+        });
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_compare_old").set_onclick(Some(btn_compare_old_click.as_ref().unchecked_ref()));
+    btn_compare_old_click.forget();
+
+    //点击差异列表跳转到对应的字
+    let on_diff_change = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            let ch = diff_list().value();
+            match ch.chars().next().filter(|ch| app_data.strokes.contains_key(ch)){
+                Some(key) => {
+                    select_char(&app_data, key)?;
+                    draw_ch(&app_data, ch, true, true)
+                }
+                None => Ok(JsValue::FALSE)
+            }
+        }).expect("字符绘制失败");
+    }) as Box<dyn FnMut()>);
+    diff_list().set_onchange(Some(on_diff_change.as_ref().unchecked_ref()));
+    on_diff_change.forget();
+
+    //关闭对比
+    let btn_close_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            app_data.compare.clear();
+            get_element_by_id("compare").style().set_property("display", "none")?;
+            draw_ch(&app_data, app_data.select.value(), false, false)
+        }).expect("关闭对比失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_close_compare").set_onclick(Some(btn_close_click.as_ref().unchecked_ref()));
+    btn_close_click.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+fn diff_list() -> HtmlSelectElement{
+    get_element_by_id("select_diff").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+//显示对比区域并填充差异列表, 每一项是"字 说明"
+fn show(app_data:&AppData, items: Vec<(char, String)>) -> Result<(), JsValue>{
+    let list = diff_list();
+    list.set_text_content(None);
+    for (ch, description) in items{
+        let option = app_data.document.create_element("option")?;
+        option.set_attribute("value", &ch.to_string())?;
+        option.set_text_content(Some(&format!("{} {}", ch, description)));
+        list.append_child(&option)?;
+    }
+    get_element_by_id("compare").style().set_property("display", "inline-block")?;
+    Ok(())
+}
+
+//打开另一个STROKES.data和当前数据对比
+fn open_compare(data: Vec<u8>) -> Result<JsValue, JsValue>{
+    let list = match read_any(&data){
+        Ok((list, _)) => list,
+        Err(err) => {
+            alert(&err);
+            return Ok(JsValue::FALSE);
+        }
+    };
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        app_data.compare = list.into_iter().collect();
+        let diffs = diff(&app_data.compare, &app_data.strokes, &app_data.chars, TOLERANCE);
+        show(&app_data, diffs.iter().map(|(ch, diff)| (*ch, diff.to_string())).collect())?;
+        alert(&format!("共{}个字有差异", diffs.len()));
+        draw_ch(&app_data, app_data.select.value(), false, false)
+    })
+}
+
+//把另一个STROKES.data合并到当前数据, 以加载时的数据作为共同的原始数据, 审核信息和异体字关系也一起合并
+fn open_merge(data: Vec<u8>) -> Result<JsValue, JsValue>{
+    let read = read_any(&data).and_then(|(list, _)| {
+        let reviews = read_reviews(&data, list_size(&list))?;
+        let variants = read_variants(&data, list_size(&list) + reviews_size(&reviews))?;
+        Ok((list, reviews, variants))
+    });
+    let (theirs, theirs_reviews, theirs_variants) = match read{
+        Ok(read) => read,
+        Err(err) => {
+            alert(&err);
+            return Ok(JsValue::FALSE);
+        }
+    };
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let mut base: Vec<(char, Strokes)> = app_data.base.iter().map(|(ch, strokes)| (*ch, strokes.clone())).collect();
+        base.sort_by_key(|(ch, _)| *ch);
+        let merged = merge(&base, &current_list(&app_data), &theirs);
+        let current = app_data.select.value();
+        app_data.chars = merged.list.iter().map(|(ch, _)| *ch).collect();
+        app_data.strokes = merged.list.into_iter().collect();
+        app_data.reviews = merge_reviews(&app_data.base_reviews, &app_data.reviews, &theirs_reviews);
+        app_data.variants.merge(&theirs_variants);
+        app_data.compare = theirs.into_iter().collect();
+        fill_chars(&app_data)?;
+        //保持原来选择的字
+        app_data.select.set_value(&current);
+        if app_data.select.value() != current{
+            app_data.select.set_selected_index(0);
+        }
+        //冲突的字保留了当前的版本, 列出来逐个检查
+        show(&app_data, merged.conflicts.iter().map(|conflict| (conflict.ch, "冲突".to_string())).collect())?;
+        api::notify("reload", None);
+        download::clear();
+        autosave::modified(&current_name(&app_data));
+        alert(&format!("合并完成, {}个冲突(保留当前的版本)", merged.conflicts.len()));
+        draw_ch(&app_data, app_data.select.value(), true, true)
+    })
+}
+
+//在对比画布上用相同的视图绘制对比数据中的字
+pub fn draw(app_data:&AppData, ch: char) -> Result<JsValue, JsValue>{
+    if app_data.compare.is_empty(){
+        return Ok(JsValue::FALSE);
+    }
+    let strokes = app_data.compare.get(&ch);
+    //和当前数据不同的字用蓝色绘制
+    let same = strokes.is_some() && app_data.strokes.get(&ch) == strokes;
+    draw_glyph(app_data, &app_data.compare_canvas, &app_data.compare_context, ch, strokes, if same { "#000" } else { "#00f" })
+}
+
+//在另一个画布上用和编辑画布相同的大小和视图绘制一个字, 没有笔画时显示"无"
+pub fn draw_glyph(app_data:&AppData, canvas:&HtmlCanvasElement, context:&CanvasRenderingContext2d, ch: char, strokes: Option<&Strokes>, color:&str) -> Result<JsValue, JsValue>{
+    let (width, height) = (app_data.canvas.width(), app_data.canvas.height());
+    if canvas.width() != width || canvas.height() != height{
+        canvas.set_width(width);
+        canvas.set_height(height);
+        let style = canvas.style();
+        style.set_property("width", &format!("{}px", width as f64 / app_data.pixel_ratio))?;
+        style.set_property("height", &format!("{}px", height as f64 / app_data.pixel_ratio))?;
+    }
+    let view = app_data.view;
+    context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+    context.clear_rect(0.0, 0.0, width as f64, height as f64);
+    let (scale, ratio) = (view.scale * app_data.pixel_ratio, app_data.pixel_ratio);
+    context.set_transform(scale, 0.0, 0.0, scale, view.offset_x * ratio, view.offset_y * ratio)?;
+    draw_reference(context, view.scale, ch, current_script(app_data).of(ch), true)?;
+
+    let strokes = match strokes{
+        Some(strokes) => strokes,
+        None => {
+            context.set_fill_style_str("#c00");
+            context.fill_text("无", GLYPH_SIZE * 0.5, GLYPH_SIZE * 0.75)?;
+            return Ok(JsValue::FALSE);
+        }
+    };
+    context.set_stroke_style_str(color);
+    context.set_line_width(3.0 / view.scale);
+    //跳过没有点的笔画
+    for stroke in strokes{
+        let start = match stroke.first(){
+            Some(start) => start,
+            None => continue
+        };
+        context.begin_path();
+        context.move_to(start.0 as f64, start.1 as f64);
+        for point in stroke.iter().skip(1){
+            context.line_to(point.0 as f64, point.1 as f64);
+        }
+        context.stroke();
+    }
+    Ok(JsValue::TRUE)
+}
@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use crate::{APP_DATA, alert, bind_open_file, download, draw_ch, fill_chars, filter, get_element_by_id, select_char};
+use crate::coverage::{Coverage, format_list};
+use crate::dataset::current_script;
+
+thread_local!{
+    //最近一次的报告
+    static REPORT: RefCell<Option<Coverage>> = const { RefCell::new(None) };
+}
+
+pub fn bind() -> Result<JsValue, JsValue>{
+    bind_open_file("btn_coverage", "coverage_file", open_text);
+
+    let btn_export_missing_click = Closure::wrap(Box::new(move || {
+        let missing = REPORT.with(|report| report.borrow().as_ref().map(|report| format_list(&report.missing)));
+        if let Some(missing) = missing{
+            download::download("MISSING.txt", "MISSING.txt(缺少的字)", missing.as_bytes()).expect("导出失败");
+        }
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_export_missing").set_onclick(Some(btn_export_missing_click.as_ref().unchecked_ref()));
+    btn_export_missing_click.forget();
+
+    //把未确认或者有问题的字放到队列中检查
+    let btn_queue_unreviewed_click = Closure::wrap(Box::new(move || {
+        queue(true).expect("检查失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_queue_unreviewed").set_onclick(Some(btn_queue_unreviewed_click.as_ref().unchecked_ref()));
+    btn_queue_unreviewed_click.forget();
+
+    let btn_queue_lint_click = Closure::wrap(Box::new(move || {
+        queue(false).expect("检查失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_queue_lint").set_onclick(Some(btn_queue_lint_click.as_ref().unchecked_ref()));
+    btn_queue_lint_click.forget();
+
+    let btn_close_click = Closure::wrap(Box::new(move || {
+        get_element_by_id("coverage").style().set_property("display", "none").expect("关闭报告失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_close_coverage").set_onclick(Some(btn_close_click.as_ref().unchecked_ref()));
+    btn_close_click.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+//列表中只显示前面的字
+const MAX_SHOWN: usize = 500;
+
+fn show_list(id:&str, list:&[(char, usize)]){
+    let mut text: String = list.iter().take(MAX_SHOWN).map(|(ch, count)| format!("{}{} ", ch, count)).collect();
+    if list.len() > MAX_SHOWN{
+        text.push_str(&format!("…(共{}个)", list.len()));
+    }
+    get_element_by_id(id).set_inner_text(&text);
+}
+
+//读取UTF-8文本文件, 和当前数据比较
+fn open_text(data: Vec<u8>) -> Result<JsValue, JsValue>{
+    let text = match String::from_utf8(data){
+        Ok(text) => text,
+        Err(_) => {
+            alert("只支持UTF-8编码的文本文件!");
+            return Ok(JsValue::FALSE);
+        }
+    };
+    let report = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        Coverage::new(&text, &app_data.strokes, &app_data.reviews, &current_script(&app_data))
+    });
+    get_element_by_id("coverage_summary").set_inner_text(&report.summary());
+    show_list("coverage_missing", &report.missing);
+    show_list("coverage_unreviewed", &report.unreviewed);
+    show_list("coverage_lint", &report.lint);
+    get_element_by_id("coverage").style().set_property("display", "block")?;
+    REPORT.with(|current| *current.borrow_mut() = Some(report));
+    Ok(JsValue::TRUE)
+}
+
+fn queue(unreviewed: bool) -> Result<JsValue, JsValue>{
+    let chars: Vec<char> = REPORT.with(|report| {
+        report.borrow().as_ref().map(|report| {
+            let list = if unreviewed { &report.unreviewed } else { &report.lint };
+            list.iter().map(|(ch, _)| *ch).collect()
+        }).unwrap_or_default()
+    });
+    let first = match chars.first(){
+        Some(ch) => *ch,
+        None => return Ok(JsValue::FALSE)
+    };
+    filter::set_queue(Some((if unreviewed { "文本中未确认的字" } else { "文本中有问题的字" }.to_string(), chars)));
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let app_data = app_data.borrow();
+        fill_chars(&app_data)?;
+        select_char(&app_data, first)?;
+        draw_ch(&app_data, first.to_string(), true, true)
+    })
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use js_sys::Uint8Array;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{JsFuture, spawn_local};
+use web_sys::{DragEvent, File, HtmlInputElement, HtmlSelectElement, UrlSearchParams};
+use bincode::deserialize;
+use crate::{APP_DATA, AppData, alert, api, autosave, draw_ch, fill_chars, get_element_by_id, log};
+use crate::index::CharIndex;
+use crate::data::{Strokes, list_size, read_any, write_list};
+use crate::review::{Reviews, append_reviews, read_reviews, reviews_size};
+use crate::variant::{Variants, append_variants, read_variants};
+use crate::script::{DataScript, Script};
+
+//默认加载的数据
+const DEFAULT_DATA: &str = "STROKES.data";
+//保存每个数据指定的文字的localStorage键
+const SCRIPT_KEY: &str = "script";
+
+//一个打开的数据文件
+#[derive(Default)]
+pub struct Dataset{
+    pub name: String,
+    pub chars: CharIndex,
+    pub strokes: HashMap<char, Strokes>,
+    pub base: HashMap<char, Strokes>,
+    pub widths: HashMap<char, HashMap<(u16, u16), u8>>,
+    pub reviews: Reviews,
+    pub base_reviews: Reviews,
+    pub variants: Variants,
+    pub base_variants: Variants,
+    //数据使用的文字, 一直保存在这里
+    pub script: DataScript,
+}
+
+//页面地址中?data=指定的数据文件, 没有指定时加载STROKES.data
+pub fn url_dataset() -> String{
+    web_sys::window().unwrap().location().search().ok()
+        .and_then(|search| UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get("data"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_DATA.to_string())
+}
+
+//解析数据文件, 有自动保存的编辑时询问是否恢复, 返回(数据, 是否恢复了编辑)
+pub async fn load(name:&str, data:&[u8]) -> Result<(Dataset, bool), JsValue>{
+    //加载的原始数据
+    let (base, order) = read_any(data)?;
+    let base_reviews = read_reviews(data, list_size(&base))?;
+    let base_variants = read_variants(data, list_size(&base) + reviews_size(&base_reviews))?;
+    log(&format!("{}: 字符顺序{:?}, 字符个数{}, 审核信息{}", name, order, base.len(), base_reviews.len()));
+    //恢复自动保存的编辑
+    let mut loaded = write_list(&base);
+    append_reviews(&mut loaded, &base_reviews);
+    append_variants(&mut loaded, &base_variants);
+    let (strokes, reviews, variants, widths) = match autosave::restore(name, &loaded).await{
+        Ok(Some((saved, widths))) => {
+            let (strokes, _) = read_any(&saved)?;
+            let reviews = read_reviews(&saved, list_size(&strokes))?;
+            let variants = read_variants(&saved, list_size(&strokes) + reviews_size(&reviews))?;
+            (strokes, reviews, variants, Some(widths))
+        }
+        Ok(None) => (base.clone(), base_reviews.clone(), base_variants.clone(), None),
+        Err(err) => {
+            log(&format!("读取自动保存失败:{:?}", err));
+            (base.clone(), base_reviews.clone(), base_variants.clone(), None)
+        }
+    };
+    let chars: Vec<char> = strokes.iter().map(|(ch, _)| *ch).collect();
+    let dataset = Dataset{
+        name: name.to_string(),
+        script: DataScript::detect(&chars, stored_script(name)),
+        chars: chars.into_iter().collect(),
+        strokes: strokes.into_iter().collect(),
+        base: base.into_iter().collect(),
+        widths: widths.as_ref().and_then(|widths| deserialize(widths).ok()).unwrap_or_default(),
+        reviews,
+        base_reviews,
+        variants,
+        base_variants,
+    };
+    Ok((dataset, widths.is_some()))
+}
+
+//把当前编辑的数据放回datasets
+fn store(app_data:&mut AppData){
+    let index = app_data.dataset;
+    let dataset = &mut app_data.datasets[index];
+    dataset.chars = std::mem::take(&mut app_data.chars);
+    dataset.strokes = std::mem::take(&mut app_data.strokes);
+    dataset.base = std::mem::take(&mut app_data.base);
+    dataset.widths = std::mem::take(&mut app_data.widths);
+    dataset.reviews = std::mem::take(&mut app_data.reviews);
+    dataset.base_reviews = std::mem::take(&mut app_data.base_reviews);
+    dataset.variants = std::mem::take(&mut app_data.variants);
+    dataset.base_variants = std::mem::take(&mut app_data.base_variants);
+}
+
+//从datasets中取出第index个数据进行编辑
+fn activate(app_data:&mut AppData, index: usize){
+    let dataset = &mut app_data.datasets[index];
+    let (chars, strokes) = (std::mem::take(&mut dataset.chars), std::mem::take(&mut dataset.strokes));
+    let (base, widths) = (std::mem::take(&mut dataset.base), std::mem::take(&mut dataset.widths));
+    app_data.chars = chars;
+    app_data.strokes = strokes;
+    app_data.base = base;
+    app_data.widths = widths;
+    app_data.reviews = std::mem::take(&mut app_data.datasets[index].reviews);
+    app_data.base_reviews = std::mem::take(&mut app_data.datasets[index].base_reviews);
+    app_data.variants = std::mem::take(&mut app_data.datasets[index].variants);
+    app_data.base_variants = std::mem::take(&mut app_data.datasets[index].base_variants);
+    app_data.dataset = index;
+}
+
+//当前编辑的数据的名字
+pub fn current_name(app_data:&AppData) -> String{
+    app_data.datasets.get(app_data.dataset).map(|dataset| dataset.name.clone()).unwrap_or_default()
+}
+
+//当前编辑的数据使用的文字
+pub fn current_script(app_data:&AppData) -> DataScript{
+    app_data.datasets.get(app_data.dataset).map(|dataset| dataset.script).unwrap_or_default()
+}
+
+fn storage() -> Option<web_sys::Storage>{
+    web_sys::window().unwrap().local_storage().ok().flatten()
+}
+
+//用户为数据指定的文字, 没有指定时为None(自动)
+fn stored_script(name:&str) -> Option<Script>{
+    storage().and_then(|storage| storage.get_item(&format!("{}:{}", SCRIPT_KEY, name)).ok().flatten())
+        .and_then(|id| Script::from_id(&id))
+}
+
+//添加数据并切换到这个数据, 同名的数据会被替换
+pub fn add(app_data:&mut AppData, dataset: Dataset){
+    if !app_data.datasets.is_empty(){
+        store(app_data);
+    }
+    let index = match app_data.datasets.iter().position(|d| d.name == dataset.name){
+        Some(index) => {
+            app_data.datasets[index] = dataset;
+            index
+        }
+        None => {
+            app_data.datasets.push(dataset);
+            app_data.datasets.len() - 1
+        }
+    };
+    activate(app_data, index);
+}
+
+//数据列表
+fn select_dataset() -> HtmlSelectElement{
+    get_element_by_id("select_dataset").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+//数据使用的文字
+fn select_script() -> HtmlSelectElement{
+    get_element_by_id("select_script").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+pub fn fill_datasets(app_data:&AppData) -> Result<(), JsValue>{
+    let select = select_dataset();
+    select.set_text_content(None);
+    for dataset in &app_data.datasets{
+        let option = app_data.document.create_element("option")?;
+        option.set_text_content(Some(&dataset.name));
+        select.append_child(&option)?;
+    }
+    select.set_selected_index(app_data.dataset as i32);
+    select_script().set_value(current_script(app_data).fixed.map(|script| script.id()).unwrap_or(""));
+    Ok(())
+}
+
+//切换数据后重新显示字符列表
+fn show(app_data:&AppData) -> Result<JsValue, JsValue>{
+    api::notify("reload", None);
+    autosave::show_status(&current_name(app_data));
+    fill_datasets(app_data)?;
+    fill_chars(app_data)?;
+    app_data.select.set_selected_index(0);
+    draw_ch(app_data, app_data.select.value(), true, true)
+}
+
+//打开本地文件
+async fn open_file(file: File) -> Result<JsValue, JsValue>{
+    let name = file.name();
+    let buffer = JsFuture::from(file.array_buffer()).await?;
+    let data = Uint8Array::new(&buffer).to_vec();
+    let exists = APP_DATA.with(|app_data| app_data.borrow().datasets.iter().any(|dataset| dataset.name == name));
+    if exists && !web_sys::window().unwrap().confirm_with_message(&format!("{}已经打开, 是否重新加载?", name))?{
+        return Ok(JsValue::FALSE);
+    }
+    //切换前保存当前数据的编辑
+    autosave::flush();
+    let (dataset, restored) = match load(&name, &data).await{
+        Ok(result) => result,
+        Err(err) => {
+            alert(&format!("{}打开失败: {}", name, err.as_string().unwrap_or_default()));
+            return Ok(JsValue::FALSE);
+        }
+    };
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        add(&mut app_data, dataset);
+        show(&app_data)
+    })?;
+    if restored{
+        autosave::modified(&name);
+    }
+    Ok(JsValue::TRUE)
+}
+
+fn open_files(files: Vec<File>){
+    spawn_local(async move{
+        //逐个打开, 避免同时弹出多个确认框
+        for file in files{
+            open_file(file).await.expect("打开文件失败");
+        }
+    });
+}
+
+pub fn bind() -> Result<JsValue, JsValue>{
+    //切换数据
+    let on_dataset_change = Closure::wrap(Box::new(move || {
+        autosave::flush();
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            let index = select_dataset().selected_index();
+            if index < 0 || index as usize == app_data.dataset{
+                return Ok(JsValue::FALSE);
+            }
+            store(&mut app_data);
+            activate(&mut app_data, index as usize);
+            show(&app_data)
+        }).expect("切换数据失败");
+    }) as Box<dyn FnMut()>);
+    select_dataset().set_onchange(Some(on_dataset_change.as_ref().unchecked_ref()));
+    on_dataset_change.forget();
+
+    //指定数据使用的文字
+    let select = select_script();
+    for (id, name) in std::iter::once(("", "自动")).chain(Script::ALL.iter().map(|script| (script.id(), script.name()))){
+        let option = web_sys::window().unwrap().document().unwrap().create_element("option")?;
+        option.set_attribute("value", id)?;
+        option.set_text_content(Some(name));
+        select.append_child(&option)?;
+    }
+    let on_script_change = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            let fixed = Script::from_id(&select_script().value());
+            let name = current_name(&app_data);
+            if let Some(storage) = storage(){
+                let key = format!("{}:{}", SCRIPT_KEY, name);
+                match fixed{
+                    Some(script) => storage.set_item(&key, script.id())?,
+                    None => storage.remove_item(&key)?
+                }
+            }
+            let index = app_data.dataset;
+            if let Some(dataset) = app_data.datasets.get_mut(index){
+                dataset.script.fixed = fixed;
+            }
+            //筛选和显示都和文字有关
+            let current = app_data.select.value();
+            fill_chars(&app_data)?;
+            app_data.select.set_value(&current);
+            draw_ch(&app_data, app_data.select.value(), false, false)
+        }).expect("设置文字失败");
+    }) as Box<dyn FnMut()>);
+    select_script().set_onchange(Some(on_script_change.as_ref().unchecked_ref()));
+    on_script_change.forget();
+
+    //选择本地文件
+    let input = get_element_by_id("data_file").dyn_into::<HtmlInputElement>().unwrap();
+    let file_input = input.clone();
+    let btn_open_click = Closure::wrap(Box::new(move || {
+        file_input.click();
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_open").set_onclick(Some(btn_open_click.as_ref().unchecked_ref()));
+    btn_open_click.forget();
+
+    let file_input = input.clone();
+    let on_file_change = Closure::wrap(Box::new(move || {
+        if let Some(files) = file_input.files(){
+            open_files((0..files.length()).filter_map(|i| files.get(i)).collect());
+        }
+        file_input.set_value("");
+    }) as Box<dyn FnMut()>);
+    input.set_onchange(Some(on_file_change.as_ref().unchecked_ref()));
+    on_file_change.forget();
+
+    //拖放文件到页面上打开
+    let body = web_sys::window().unwrap().document().unwrap().body().unwrap();
+    let on_drag_over = Closure::wrap(Box::new(move |event: DragEvent| {
+        event.prevent_default();
+    }) as Box<dyn FnMut(_)>);
+    body.set_ondragover(Some(on_drag_over.as_ref().unchecked_ref()));
+    on_drag_over.forget();
+
+    let on_drop = Closure::wrap(Box::new(move |event: DragEvent| {
+        event.prevent_default();
+        if let Some(files) = event.data_transfer().and_then(|data| data.files()){
+            open_files((0..files.length()).filter_map(|i| files.get(i)).collect());
+        }
+    }) as Box<dyn FnMut(_)>);
+    body.set_ondrop(Some(on_drop.as_ref().unchecked_ref()));
+    on_drop.forget();
+
+    Ok(JsValue::TRUE)
+}
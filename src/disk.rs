@@ -0,0 +1,87 @@
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use crate::idb;
+
+//IndexedDB中保存的文件句柄, 每个数据文件一条
+const HANDLE_KEY: &str = "handle";
+
+//保存的结果
+pub enum Saved{
+    //写入了文件(文件名)
+    Written(String),
+    //用户取消了选择文件
+    Cancelled,
+    //浏览器不支持File System Access
+    Unsupported,
+}
+
+fn key(name:&str) -> String{
+    format!("{}:{}", HANDLE_KEY, name)
+}
+
+//调用对象的方法并等待返回的Promise
+async fn call(target:&JsValue, method:&str, args:&Array) -> Result<JsValue, JsValue>{
+    let function: Function = Reflect::get(target, &method.into())?.dyn_into()?;
+    let promise: Promise = function.apply(target, args)?.dyn_into()?;
+    JsFuture::from(promise).await
+}
+
+fn supported() -> bool{
+    Reflect::has(&web_sys::window().unwrap(), &"showSaveFilePicker".into()).unwrap_or(false)
+}
+
+//选择保存的文件, 取消时返回None
+async fn pick(name:&str) -> Result<Option<JsValue>, JsValue>{
+    let accept = Object::new();
+    Reflect::set(&accept, &"application/octet-stream".into(), &Array::of2(&".data".into(), &".map".into()))?;
+    let file_type = Object::new();
+    Reflect::set(&file_type, &"description".into(), &"笔画数据".into())?;
+    Reflect::set(&file_type, &"accept".into(), &accept)?;
+    let options = Object::new();
+    //页面地址中的数据名可能带有路径
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    Reflect::set(&options, &"suggestedName".into(), &file_name.into())?;
+    Reflect::set(&options, &"types".into(), &Array::of1(&file_type))?;
+    match call(&web_sys::window().unwrap(), "showSaveFilePicker", &Array::of1(&options)).await{
+        Ok(handle) => Ok(Some(handle)),
+        Err(err) if Reflect::get(&err, &"name".into())? == "AbortError" => Ok(None),
+        Err(err) => Err(err)
+    }
+}
+
+//检查写入权限, 没有权限时请求
+async fn permitted(handle:&JsValue) -> Result<bool, JsValue>{
+    let options = Object::new();
+    Reflect::set(&options, &"mode".into(), &"readwrite".into())?;
+    let args = Array::of1(&options);
+    if call(handle, "queryPermission", &args).await? == "granted"{
+        return Ok(true);
+    }
+    Ok(call(handle, "requestPermission", &args).await? == "granted")
+}
+
+async fn write(handle:&JsValue, data:&[u8]) -> Result<(), JsValue>{
+    let writable = call(handle, "createWritable", &Array::new()).await?;
+    call(&writable, "write", &Array::of1(&Uint8Array::from(data))).await?;
+    call(&writable, "close", &Array::new()).await?;
+    Ok(())
+}
+
+//保存到上次保存的文件, 没有保存过或者save_as时先选择文件
+pub async fn save(name:&str, data:&[u8], save_as: bool) -> Result<Saved, JsValue>{
+    if !supported(){
+        return Ok(Saved::Unsupported);
+    }
+    let mut handle = if save_as { JsValue::UNDEFINED } else { idb::get(&key(name)).await? };
+    if handle.is_undefined() || !permitted(&handle).await?{
+        handle = match pick(name).await?{
+            Some(handle) => handle,
+            None => return Ok(Saved::Cancelled)
+        };
+        idb::put(&key(name), &handle).await?;
+    }
+    write(&handle, data).await?;
+    Ok(Saved::Written(Reflect::get(&handle, &"name".into())?.as_string().unwrap_or_default()))
+}
@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+use crate::get_element_by_id;
+
+thread_local!{
+    //download_button当前链接的Blob地址, 替换时释放
+    static OBJECT_URL: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn link() -> HtmlAnchorElement{
+    get_element_by_id("download_button").dyn_into::<HtmlAnchorElement>().unwrap()
+}
+
+//把数据放到Blob中并立即下载, 下载链接保留在页面上可以再次点击
+pub fn download(name:&str, text:&str, data:&[u8]) -> Result<(), JsValue>{
+    let parts = Array::of1(&Uint8Array::from(data));
+    let options = BlobPropertyBag::new();
+    options.set_type("application/octet-stream");
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    clear();
+    let link = link();
+    link.set_href(&url);
+    link.set_download(name);
+    link.set_inner_text(text);
+    OBJECT_URL.with(|object_url| *object_url.borrow_mut() = Some(url));
+    link.click();
+    Ok(())
+}
+
+//数据修改后清除下载链接
+pub fn clear(){
+    if let Some(url) = OBJECT_URL.with(|object_url| object_url.borrow_mut().take()){
+        let _ = Url::revoke_object_url(&url);
+    }
+    let link = link();
+    link.set_href("");
+    link.set_inner_text("");
+}
@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use crate::{APP_DATA, AppData, annotation, change_class, current_char, draw_ch, fill_chars, get_element_by_id};
+use crate::charset::{Charset, charset};
+use crate::dataset::current_script;
+use crate::script::lint;
+use crate::review::Status;
+
+//排序方式: (id, 名字), 默认按Unicode顺序(字符索引的顺序), 有队列时按队列的顺序
+const SORTS: [(&str, &str); 3] = [
+    ("", "默认顺序"),
+    ("strokes", "笔画数"),
+    ("review_time", "最近审核"),
+];
+
+thread_local!{
+    //搜索结果或者粘贴的文本: (说明, 字), 有队列时只显示队列中的字
+    static QUEUE: RefCell<Option<(String, Vec<char>)>> = const { RefCell::new(None) };
+}
+
+//字符列表的筛选条件
+pub struct Filter{
+    queue: Option<Vec<char>>,
+    min_strokes: Option<usize>,
+    max_strokes: Option<usize>,
+    lint: bool,
+    modified: bool,
+    status: Option<Status>,
+    charset: Option<Charset>,
+    sort: String,
+}
+
+fn select(id:&str) -> HtmlSelectElement{
+    get_element_by_id(id).dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+fn input(id:&str) -> HtmlInputElement{
+    get_element_by_id(id).dyn_into::<HtmlInputElement>().unwrap()
+}
+
+impl Filter{
+    //读取页面中的筛选条件
+    pub fn current() -> Filter{
+        Filter{
+            queue: QUEUE.with(|queue| queue.borrow().as_ref().map(|(_, chars)| chars.clone())),
+            min_strokes: input("filter_min_strokes").value().trim().parse().ok(),
+            max_strokes: input("filter_max_strokes").value().trim().parse().ok(),
+            lint: input("filter_lint").checked(),
+            modified: input("filter_modified").checked(),
+            status: Status::from_id(&select("filter_status").value()),
+            charset: Charset::from_id(&select("filter_charset").value()),
+            sort: select("sort_chars").value(),
+        }
+    }
+
+    pub fn accept(&self, app_data:&AppData, ch: char) -> bool{
+        let strokes = match app_data.strokes.get(&ch){
+            Some(strokes) => strokes,
+            None => return false
+        };
+        if self.min_strokes.map(|min| strokes.len() < min).unwrap_or(false)
+            || self.max_strokes.map(|max| strokes.len() > max).unwrap_or(false){
+            return false;
+        }
+        if let Some(status) = self.status{
+            if app_data.reviews.get(&ch).map(|review| review.status).unwrap_or_default() != status{
+                return false;
+            }
+        }
+        if self.charset.map(|set| charset(ch) != set).unwrap_or(false){
+            return false;
+        }
+        if self.modified && change_class(app_data, ch).is_empty(){
+            return false;
+        }
+        //检查问题最慢, 放在最后
+        if self.lint && lint(current_script(app_data).of(ch), strokes).is_empty(){
+            return false;
+        }
+        true
+    }
+
+    //筛选并排序后的字符
+    pub fn apply(&self, app_data:&AppData) -> Vec<char>{
+        let chars = self.queue.as_deref().unwrap_or(&app_data.chars);
+        let mut chars: Vec<char> = chars.iter().cloned().filter(|ch| self.accept(app_data, *ch)).collect();
+        match self.sort.as_str(){
+            //sort_by_key是稳定排序, 相同笔画数的字保持原来的顺序
+            "strokes" => chars.sort_by_key(|ch| app_data.strokes[ch].len()),
+            "review_time" => chars.sort_by(|a, b| {
+                let time = |ch| app_data.reviews.get(ch).map(|review| review.time).unwrap_or(0.0);
+                time(b).total_cmp(&time(a))
+            }),
+            _ => ()
+        }
+        chars
+    }
+}
+
+//设置或者清除队列
+pub fn set_queue(queue: Option<(String, Vec<char>)>){
+    get_element_by_id("btn_clear_queue").style().set_property("display", if queue.is_some() { "" } else { "none" }).unwrap();
+    QUEUE.with(|current| *current.borrow_mut() = queue);
+}
+
+//清除所有筛选条件
+pub fn reset(){
+    set_queue(None);
+    input("filter_min_strokes").set_value("");
+    input("filter_max_strokes").set_value("");
+    input("filter_lint").set_checked(false);
+    input("filter_modified").set_checked(false);
+    select("filter_status").set_value("");
+    select("filter_charset").set_value("");
+}
+
+//显示筛选后的字数
+pub fn show_count(app_data:&AppData, count: usize){
+    let mut text = if count == app_data.chars.len(){
+        format!("{}个字", count)
+    }else{
+        format!("{}/{}个字", count, app_data.chars.len())
+    };
+    if let Some(label) = QUEUE.with(|queue| queue.borrow().as_ref().map(|(label, _)| label.clone())){
+        text = format!("{}: {}", label, text);
+    }
+    get_element_by_id("filter_count").set_inner_text(&text);
+}
+
+fn add_option(app_data:&AppData, select:&HtmlSelectElement, value:&str, text:&str) -> Result<(), JsValue>{
+    let option = app_data.document.create_element("option")?;
+    option.set_attribute("value", value)?;
+    option.set_text_content(Some(text));
+    select.append_child(&option)?;
+    Ok(())
+}
+
+//筛选条件改变后重新填充字符列表, 当前的字还在列表中时保持选择
+fn refresh() -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let app_data = app_data.borrow();
+        let current = app_data.select.value();
+        fill_chars(&app_data)?;
+        app_data.select.set_value(&current);
+        if app_data.select.value() != current{
+            app_data.select.set_selected_index(0);
+        }
+        //没有符合条件的字时清空画布
+        match current_char(&app_data){
+            Some(ch) => draw_ch(&app_data, ch.to_string(), true, true),
+            None => draw_ch(&app_data, String::new(), true, true)
+        }
+    })
+}
+
+pub fn bind(app_data:&AppData) -> Result<JsValue, JsValue>{
+    let status = select("filter_status");
+    add_option(app_data, &status, "", "全部状态")?;
+    annotation::fill_status(app_data, &status)?;
+    let charsets = select("filter_charset");
+    add_option(app_data, &charsets, "", "全部字符集")?;
+    for set in Charset::ALL.iter(){
+        add_option(app_data, &charsets, set.id(), set.name())?;
+    }
+    let sorts = select("sort_chars");
+    for (id, name) in SORTS.iter(){
+        add_option(app_data, &sorts, id, name)?;
+    }
+
+    let on_filter_change = Closure::wrap(Box::new(move || {
+        refresh().expect("筛选失败");
+    }) as Box<dyn FnMut()>);
+    for id in ["filter_status", "filter_charset", "sort_chars", "filter_min_strokes", "filter_max_strokes", "filter_lint", "filter_modified"].iter(){
+        get_element_by_id(id).set_onchange(Some(on_filter_change.as_ref().unchecked_ref()));
+    }
+    on_filter_change.forget();
+
+    let btn_clear_queue_click = Closure::wrap(Box::new(move || {
+        set_queue(None);
+        refresh().expect("筛选失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_clear_queue").set_onclick(Some(btn_clear_queue_click.as_ref().unchecked_ref()));
+    btn_clear_queue_click.forget();
+
+    Ok(JsValue::TRUE)
+}
@@ -0,0 +1,64 @@
+use js_sys::{Function, Promise};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+//IndexedDB中的数据库和表
+const DB_NAME: &str = "stroke_editor";
+const STORE_NAME: &str = "kv";
+
+//等待IdbRequest完成, 返回request.result
+async fn wait(request:&IdbRequest) -> Result<JsValue, JsValue>{
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move |_event: JsValue| {
+            let _ = resolve.call1(&JsValue::NULL, &success_request.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let error_request = request.clone();
+        let on_error = Closure::once_into_js(move |_event: JsValue| {
+            let error = error_request.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await
+}
+
+async fn open() -> Result<IdbDatabase, JsValue>{
+    let factory = web_sys::window().unwrap().indexed_db()?.ok_or_else(|| JsValue::from_str("浏览器不支持IndexedDB"))?;
+    let request: IdbOpenDbRequest = factory.open_with_u32(DB_NAME, 1)?;
+    //第一次打开时创建表
+    let upgrade_request = request.clone();
+    let on_upgrade = Closure::once_into_js(move |_event: JsValue| {
+        if let Ok(db) = upgrade_request.result().and_then(|db| db.dyn_into::<IdbDatabase>()){
+            let _ = db.create_object_store(STORE_NAME);
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    wait(&request).await?.dyn_into::<IdbDatabase>()
+}
+
+async fn store(mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue>{
+    let db = open().await?;
+    db.transaction_with_str_and_mode(STORE_NAME, mode)?.object_store(STORE_NAME)
+}
+
+//读取, 不存在时返回undefined
+pub async fn get(key:&str) -> Result<JsValue, JsValue>{
+    let store = store(IdbTransactionMode::Readonly).await?;
+    wait(&store.get(&JsValue::from_str(key))?).await
+}
+
+pub async fn put(key:&str, value:&JsValue) -> Result<(), JsValue>{
+    let store = store(IdbTransactionMode::Readwrite).await?;
+    wait(&store.put_with_key(value, &JsValue::from_str(key))?).await?;
+    Ok(())
+}
+
+pub async fn delete(key:&str) -> Result<(), JsValue>{
+    let store = store(IdbTransactionMode::Readwrite).await?;
+    wait(&store.delete(&JsValue::from_str(key))?).await?;
+    Ok(())
+}
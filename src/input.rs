@@ -0,0 +1,218 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlCanvasElement, MouseEvent, PointerEvent, WheelEvent};
+use crate::{APP_DATA, AppData, current_char, draw_ch, insert_stroke, practice, pressure_to_width};
+use crate::view::GLYPH_SIZE;
+use crate::simplify::simplify_to;
+
+//滚轮每格的缩放倍数
+const ZOOM_STEP: f64 = 1.2;
+//指针移动超过这个距离(画布CSS像素)才算拖动
+const DRAG_THRESHOLD: f64 = 4.0;
+//描画笔画简化的容差(字形坐标)
+const TRACE_TOLERANCE: f64 = 8.0;
+//描画的一笔最多保留的点数
+const TRACE_MAX_POINTS: usize = 16;
+
+//绑定画布的指针(鼠标、触摸、笔)和滚轮事件
+pub fn bind(canvas:&HtmlCanvasElement) -> Result<(), JsValue>{
+    let on_pointer_down = Closure::wrap(Box::new(move |event: PointerEvent| {
+        pointer_down(&event).expect("pointerdown处理失败");
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("pointerdown", on_pointer_down.as_ref().unchecked_ref())?;
+    on_pointer_down.forget();
+
+    let on_pointer_move = Closure::wrap(Box::new(move |event: PointerEvent| {
+        pointer_move(&event).expect("pointermove处理失败");
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("pointermove", on_pointer_move.as_ref().unchecked_ref())?;
+    on_pointer_move.forget();
+
+    let on_pointer_up = Closure::wrap(Box::new(move |event: PointerEvent| {
+        pointer_up(&event, true).expect("pointerup处理失败");
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("pointerup", on_pointer_up.as_ref().unchecked_ref())?;
+    on_pointer_up.forget();
+
+    //被系统打断(比如触摸变成了页面滚动)时不算点击
+    let on_pointer_cancel = Closure::wrap(Box::new(move |event: PointerEvent| {
+        pointer_up(&event, false).expect("pointercancel处理失败");
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("pointercancel", on_pointer_cancel.as_ref().unchecked_ref())?;
+    on_pointer_cancel.forget();
+
+    //滚轮缩放
+    let on_wheel = Closure::wrap(Box::new(move |event: WheelEvent| {
+        event.prevent_default();
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            let (x, y) = event_position(&app_data.canvas, &event);
+            let factor = if event.delta_y() < 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+            app_data.view.zoom_at(x, y, factor);
+            redraw(&app_data)
+        }).expect("字符绘制失败");
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", on_wheel.as_ref().unchecked_ref())?;
+    on_wheel.forget();
+
+    Ok(())
+}
+
+//事件相对于画布左上角的CSS像素坐标(不依赖offsetX和页面布局)
+pub fn event_position(canvas:&HtmlCanvasElement, event:&MouseEvent) -> (f64, f64){
+    let rect = canvas.get_bounding_client_rect();
+    (event.client_x() as f64 - rect.left(), event.client_y() as f64 - rect.top())
+}
+
+//笔的压力(鼠标和触摸没有压力数据)
+fn pen_pressure(event:&PointerEvent) -> Option<f32>{
+    if event.pointer_type() == "pen"{
+        Some(event.pressure())
+    }else{
+        None
+    }
+}
+
+//两个指针的中点和距离
+fn pinch(pointers:&[(i32, f64, f64)]) -> ((f64, f64), f64){
+    let ((_, x1, y1), (_, x2, y2)) = (pointers[0], pointers[1]);
+    (((x1 + x2) / 2.0, (y1 + y2) / 2.0), ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt())
+}
+
+fn redraw(app_data:&AppData) -> Result<JsValue, JsValue>{
+    let ch = app_data.select.value();
+    draw_ch(app_data, ch, false, false)
+}
+
+fn pointer_down(event:&PointerEvent) -> Result<JsValue, JsValue>{
+    if event.pointer_type() == "mouse" && event.button() != 0{
+        return Ok(JsValue::FALSE);
+    }
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        //移出画布后仍然接收事件
+        app_data.canvas.set_pointer_capture(event.pointer_id())?;
+        let (x, y) = event_position(&app_data.canvas, event);
+        app_data.pointers.retain(|p| p.0 != event.pointer_id());
+        app_data.pointers.push((event.pointer_id(), x, y));
+        if app_data.pointers.len() == 1{
+            app_data.drag = Some((x, y, false));
+            app_data.pressure = pen_pressure(event);
+            if app_data.draw_mode || practice::active(){
+                //开始描画(练习时也一样)
+                let (gx, gy) = app_data.view.canvas_to_glyph(x, y);
+                app_data.trace = vec![(gx, gy, app_data.pressure)];
+            }
+        }else{
+            //多指缩放, 不再算点击, 也不再描画
+            app_data.drag = Some((x, y, true));
+            app_data.trace.clear();
+        }
+        Ok(JsValue::TRUE)
+    })
+}
+
+fn pointer_move(event:&PointerEvent) -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let index = match app_data.pointers.iter().position(|p| p.0 == event.pointer_id()){
+            Some(index) => index,
+            None => return Ok(JsValue::FALSE)
+        };
+        let (x, y) = event_position(&app_data.canvas, event);
+        if let (Some(pressure), Some(last)) = (pen_pressure(event), app_data.pressure){
+            app_data.pressure = Some(pressure.max(last));
+        }
+
+        if app_data.pointers.len() >= 2{
+            //双指: 平移并缩放
+            if index > 1{
+                return Ok(JsValue::FALSE);
+            }
+            let (old_center, old_distance) = pinch(&app_data.pointers);
+            app_data.pointers[index] = (event.pointer_id(), x, y);
+            let (center, distance) = pinch(&app_data.pointers);
+            app_data.view.pan(center.0 - old_center.0, center.1 - old_center.1);
+            if old_distance > 0.0{
+                app_data.view.zoom_at(center.0, center.1, distance / old_distance);
+            }
+            return redraw(&app_data);
+        }
+
+        app_data.pointers[index] = (event.pointer_id(), x, y);
+        //单指针: 拖动平移
+        let (last_x, last_y, dragging) = match app_data.drag{
+            Some(drag) => drag,
+            None => return Ok(JsValue::FALSE)
+        };
+        if !dragging && (x - last_x).abs() + (y - last_y).abs() < DRAG_THRESHOLD{
+            return Ok(JsValue::FALSE);
+        }
+        if !app_data.trace.is_empty(){
+            //描画模式: 记录路径
+            let (gx, gy) = app_data.view.canvas_to_glyph(x, y);
+            app_data.trace.push((gx, gy, pen_pressure(event)));
+            app_data.drag = Some((x, y, true));
+            return redraw(&app_data);
+        }
+        app_data.view.pan(x - last_x, y - last_y);
+        app_data.drag = Some((x, y, true));
+        redraw(&app_data)
+    })
+}
+
+//抬起指针, 没有拖动时设置替换点(练习时提交一个点), 描画时插入新的笔画
+fn pointer_up(event:&PointerEvent, click: bool) -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        if !app_data.pointers.iter().any(|p| p.0 == event.pointer_id()){
+            return Ok(JsValue::FALSE);
+        }
+        app_data.pointers.retain(|p| p.0 != event.pointer_id());
+        if !app_data.pointers.is_empty(){
+            return Ok(JsValue::FALSE);
+        }
+        let trace = std::mem::take(&mut app_data.trace);
+        match app_data.drag.take(){
+            //练习时点一下是写了一个点
+            Some((_, _, false)) if click && practice::active() && !trace.is_empty() => return finish_trace(&mut app_data, &trace),
+            Some((_, _, false)) if click => (),
+            Some((_, _, true)) if click && trace.len() > 1 => return finish_trace(&mut app_data, &trace),
+            _ if !trace.is_empty() => return redraw(&app_data),
+            _ => return Ok(JsValue::FALSE)
+        }
+        let (x, y) = event_position(&app_data.canvas, event);
+        let (x, y) = app_data.view.canvas_to_glyph(x, y);
+        app_data.point = Some((
+            x.round().clamp(0.0, GLYPH_SIZE) as i32,
+            y.round().clamp(0.0, GLYPH_SIZE) as i32
+        ));
+        redraw(&app_data)
+    })
+}
+
+//简化描画的路径并插入为新的笔画(练习时交给practice)
+fn finish_trace(app_data:&mut AppData, trace:&[(f64, f64, Option<f32>)]) -> Result<JsValue, JsValue>{
+    let points: Vec<(f64, f64)> = trace.iter().map(|p| {
+        (p.0.round().clamp(0.0, GLYPH_SIZE), p.1.round().clamp(0.0, GLYPH_SIZE))
+    }).collect();
+    //练习: 和数据中的笔画比较, 不修改数据
+    if practice::active(){
+        return practice::submit(app_data, &points);
+    }
+    let keep = simplify_to(&points, TRACE_TOLERANCE, TRACE_MAX_POINTS);
+    let stroke: Vec<(u16, u16)> = keep.iter().map(|&i| (points[i].0 as u16, points[i].1 as u16)).collect();
+
+    //用笔描画时记录每个点的宽度
+    let key = match current_char(app_data){
+        Some(ch) => ch,
+        //字符列表为空时清除描画的路径
+        None => return redraw(app_data)
+    };
+    for (&i, point) in keep.iter().zip(stroke.iter()){
+        if let Some(pressure) = trace[i].2{
+            app_data.widths.entry(key).or_default().insert(*point, pressure_to_width(pressure));
+        }
+    }
+    insert_stroke(app_data, stroke)
+}
@@ -0,0 +1,217 @@
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{Event, HtmlElement, HtmlInputElement, HtmlSelectElement, KeyboardEvent};
+use crate::{APP_DATA, get_element_by_id, nudge_point, save};
+
+//localStorage中保存快捷键的键名
+const STORAGE_KEY: &str = "keymap";
+
+//所有动作: (动作名, 说明, 默认按键)
+//动作名和页面中的按钮id相同时直接点击按钮
+const ACTIONS: &[(&str, &str, &str)] = &[
+    ("prev_char", "上一个字", "PageUp"),
+    ("next_char", "下一个字", "PageDown"),
+    ("prev_stroke", "上一笔", "["),
+    ("next_stroke", "下一笔", "]"),
+    ("prev_point", "上一个点", ","),
+    ("next_point", "下一个点", "."),
+    ("nudge_left", "当前点左移", "ArrowLeft"),
+    ("nudge_right", "当前点右移", "ArrowRight"),
+    ("nudge_up", "当前点上移", "ArrowUp"),
+    ("nudge_down", "当前点下移", "ArrowDown"),
+    ("nudge_left_10", "当前点左移10", "Shift+ArrowLeft"),
+    ("nudge_right_10", "当前点右移10", "Shift+ArrowRight"),
+    ("nudge_up_10", "当前点上移10", "Shift+ArrowUp"),
+    ("nudge_down_10", "当前点下移10", "Shift+ArrowDown"),
+    ("btn_move_forward", "前移笔画", "Alt+ArrowUp"),
+    ("btn_move_backward", "后移笔画", "Alt+ArrowDown"),
+    ("btn_replace", "替换当前点", "Enter"),
+    ("btn_insert_before", "在前边插入点", "Shift+I"),
+    ("btn_insert_after", "在后边插入点", "I"),
+    ("btn_delete", "删除当前点", "Delete"),
+    ("btn_add_stroke", "添加一笔", "N"),
+    ("btn_delete_stroke", "删除一笔", "Shift+Delete"),
+    ("btn_draw_mode", "描画笔画", "D"),
+    ("btn_fit_view", "适应字形", "F"),
+    ("btn_reset_view", "重置视图", "0"),
+    ("save", "保存", "Ctrl+S"),
+    ("save_as", "另存为", "Ctrl+Shift+S"),
+    ("show_keymap", "显示/隐藏快捷键", "?"),
+];
+
+thread_local!{
+    //与ACTIONS一一对应的按键
+    static KEYS: RefCell<Vec<String>> = RefCell::new(load());
+}
+
+//读取保存的快捷键, 每行一个"动作名=按键"
+fn load() -> Vec<String>{
+    let mut keys: Vec<String> = ACTIONS.iter().map(|action| action.2.to_string()).collect();
+    let storage = web_sys::window().unwrap().local_storage().ok().flatten();
+    if let Some(text) = storage.and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten()){
+        for line in text.lines(){
+            if let Some((name, key)) = line.split_once('='){
+                if let Some(index) = ACTIONS.iter().position(|action| action.0 == name){
+                    keys[index] = key.to_string();
+                }
+            }
+        }
+    }
+    keys
+}
+
+fn store(keys:&[String]){
+    let text: Vec<String> = ACTIONS.iter().zip(keys.iter()).map(|(action, key)| format!("{}={}", action.0, key)).collect();
+    if let Ok(Some(storage)) = web_sys::window().unwrap().local_storage(){
+        let _ = storage.set_item(STORAGE_KEY, &text.join("\n"));
+    }
+}
+
+//按键组合的文字表示, 如"Ctrl+Shift+ArrowLeft"
+//字母统一为大写, 其他单个字符(如"?")本身已经包含Shift
+fn combo(event:&KeyboardEvent) -> String{
+    let key = event.key();
+    let single = key.chars().count() == 1;
+    let mut combo = String::new();
+    if event.ctrl_key() || event.meta_key(){
+        combo.push_str("Ctrl+");
+    }
+    if event.alt_key(){
+        combo.push_str("Alt+");
+    }
+    if event.shift_key() && (!single || key.chars().all(|c| c.is_alphabetic())){
+        combo.push_str("Shift+");
+    }
+    if single{
+        combo.push_str(&key.to_uppercase());
+    }else{
+        combo.push_str(&key);
+    }
+    combo
+}
+
+//绑定键盘事件并生成快捷键列表
+pub fn bind() -> Result<(), JsValue>{
+    let window = web_sys::window().unwrap();
+    let on_key_down = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+        on_key_down(&event).expect("快捷键处理失败");
+    }) as Box<dyn FnMut(_)>);
+    window.add_event_listener_with_callback("keydown", on_key_down.as_ref().unchecked_ref())?;
+    on_key_down.forget();
+
+    let btn_keymap_click = Closure::wrap(Box::new(move || {
+        toggle_keymap();
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_keymap").set_onclick(Some(btn_keymap_click.as_ref().unchecked_ref()));
+    btn_keymap_click.forget();
+
+    build_keymap()
+}
+
+//生成快捷键列表, 在输入框中按下新的按键即可修改
+fn build_keymap() -> Result<(), JsValue>{
+    let document = web_sys::window().unwrap().document().unwrap();
+    let table = get_element_by_id("keymap");
+    table.set_text_content(None);
+    let keys = KEYS.with(|keys| keys.borrow().clone());
+    for (index, (action, key)) in ACTIONS.iter().zip(keys.iter()).enumerate(){
+        let row = document.create_element("tr")?;
+        let name = document.create_element("td")?;
+        name.set_text_content(Some(action.1));
+        row.append_child(&name)?;
+        let cell = document.create_element("td")?;
+        let input = document.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+        input.set_read_only(true);
+        input.set_value(key);
+        let on_input_key_down = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+            if ["Shift", "Control", "Alt", "Meta", "Tab"].contains(&event.key().as_str()){
+                return;
+            }
+            event.prevent_default();
+            let key = combo(&event);
+            KEYS.with(|keys| {
+                let mut keys = keys.borrow_mut();
+                keys[index] = key.clone();
+                store(&keys);
+            });
+            let input = event.target().unwrap().dyn_into::<HtmlInputElement>().unwrap();
+            input.set_value(&key);
+        }) as Box<dyn FnMut(_)>);
+        input.add_event_listener_with_callback("keydown", on_input_key_down.as_ref().unchecked_ref())?;
+        on_input_key_down.forget();
+        cell.append_child(&input)?;
+        row.append_child(&cell)?;
+        table.append_child(&row)?;
+    }
+    Ok(())
+}
+
+fn toggle_keymap(){
+    let keymap = get_element_by_id("keymap");
+    let hidden = keymap.style().get_property_value("display").unwrap_or_default() == "none";
+    let _ = keymap.style().set_property("display", if hidden { "table" } else { "none" });
+}
+
+fn on_key_down(event:&KeyboardEvent) -> Result<(), JsValue>{
+    //在输入框中输入时不处理
+    if let Some(target) = event.target().and_then(|target| target.dyn_into::<HtmlElement>().ok()){
+        if target.tag_name() == "INPUT" || target.tag_name() == "TEXTAREA"{
+            return Ok(());
+        }
+    }
+    let key = combo(event);
+    let action = KEYS.with(|keys| {
+        keys.borrow().iter().position(|k| *k == key).map(|index| ACTIONS[index].0)
+    });
+    if let Some(action) = action{
+        //阻止选择框、页面滚动和浏览器保存等默认操作
+        event.prevent_default();
+        run(action)?;
+    }
+    Ok(())
+}
+
+//移动选择框的选中项并触发change事件
+fn step_select(select:&HtmlSelectElement, step: i32) -> Result<(), JsValue>{
+    let len = select.length() as i32;
+    if len == 0{
+        return Ok(());
+    }
+    let index = (select.selected_index() + step).clamp(0, len - 1);
+    if index != select.selected_index(){
+        select.set_selected_index(index);
+        select.dispatch_event(&Event::new("change")?)?;
+    }
+    Ok(())
+}
+
+//执行动作
+fn run(action:&str) -> Result<(), JsValue>{
+    let (select, select_strokes, select_points) = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        (app_data.select.clone(), app_data.select_strokes.clone(), app_data.select_points.clone())
+    });
+    match action{
+        "prev_char" => step_select(&select, -1)?,
+        "next_char" => step_select(&select, 1)?,
+        "prev_stroke" => step_select(&select_strokes, -1)?,
+        "next_stroke" => step_select(&select_strokes, 1)?,
+        "prev_point" => step_select(&select_points, -1)?,
+        "next_point" => step_select(&select_points, 1)?,
+        "nudge_left" => { nudge_point(-1, 0)?; }
+        "nudge_right" => { nudge_point(1, 0)?; }
+        "nudge_up" => { nudge_point(0, -1)?; }
+        "nudge_down" => { nudge_point(0, 1)?; }
+        "nudge_left_10" => { nudge_point(-10, 0)?; }
+        "nudge_right_10" => { nudge_point(10, 0)?; }
+        "nudge_up_10" => { nudge_point(0, -10)?; }
+        "nudge_down_10" => { nudge_point(0, 10)?; }
+        "save" => { save(false)?; }
+        "save_as" => { save(true)?; }
+        "show_keymap" => toggle_keymap(),
+        //其他动作点击对应的按钮
+        id => get_element_by_id(id).click(),
+    }
+    Ok(())
+}
@@ -4,9 +4,46 @@ use js_sys::Uint8Array;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Request, RequestInit, RequestMode, Response, MouseEvent, HtmlAnchorElement, Document, HtmlElement, HtmlSelectElement, HtmlInputElement, HtmlCanvasElement, CanvasRenderingContext2d};
-use bincode::{deserialize, serialize};
-use base64::encode;
+use web_sys::{Request, RequestInit, RequestMode, Response, ResizeObserver, Document, HtmlElement, HtmlSelectElement, HtmlInputElement, HtmlCanvasElement, CanvasRenderingContext2d};
+use bincode::serialize;
+
+//不依赖浏览器的部分在stroke-core中, 命令行工具也使用
+use stroke_core::{data, patch, diff, merge, review, charset, reading, coverage, index, variant, script, matching, recognize, duplicate, simplify, view};
+mod input;
+mod keymap;
+mod idb;
+mod autosave;
+mod compare;
+mod dataset;
+mod download;
+mod disk;
+mod server;
+mod annotation;
+mod filter;
+mod search;
+mod corpus;
+mod linked;
+mod practice;
+mod api;
+mod similar;
+use data::{Strokes, StrokeList, write_list};
+use view::{View, GLYPH_SIZE, bounding_box};
+use patch::{Patch, read_patch, write_patch};
+use review::{Reviews, append_reviews};
+use variant::{Variants, append_variants};
+use index::CharIndex;
+use script::Script;
+
+//适应字形时四周的留白(字形坐标)
+const FIT_MARGIN: f64 = 60.0;
+//笔压力为1时的笔画宽度(字形坐标)
+const MAX_WIDTH: f32 = 60.0;
+//编辑当前字的按钮, 字符列表为空时禁用(快捷键点击禁用的按钮没有作用)
+const EDIT_BUTTONS: [&str; 18] = [
+    "btn_remove_char", "btn_rename_char", "btn_replace", "btn_insert_before", "btn_insert_after", "btn_delete",
+    "btn_move_forward", "btn_move_backward", "btn_move_backward2", "btn_move_backward4", "btn_move_backward3", "btn_move_backward5",
+    "btn_add_stroke", "btn_delete_stroke", "btn_draw_mode", "btn_practice", "btn_fit_view", "btn_reset_view",
+];
 
 struct AppData{
     canvas: HtmlCanvasElement,
@@ -18,8 +55,42 @@ struct AppData{
     add: HtmlInputElement,
     document: Document,
     point: Option<(i32, i32)>,
-    chars: Vec<char>,
-    strokes: HashMap<char, Vec<Vec<(u16, u16)>>>
+    //用笔点击时的压力
+    pressure: Option<f32>,
+    view: View,
+    //画布像素与CSS像素的比例(devicePixelRatio)
+    pixel_ratio: f64,
+    //描画笔画模式
+    draw_mode: bool,
+    //正在描画的路径: (字形坐标x, y, 笔的压力)
+    trace: Vec<(f64, f64, Option<f32>)>,
+    //按下的指针: (pointerId, 画布坐标x, y)
+    pointers: Vec<(i32, f64, f64)>,
+    //拖动平移: (上次的画布坐标x, y, 是否已经开始拖动)
+    drag: Option<(f64, f64, bool)>,
+    //按照字符顺序排列的所有字
+    chars: CharIndex,
+    strokes: HashMap<char, Strokes>,
+    //加载时的数据, 用来比较哪些字被修改了
+    base: HashMap<char, Strokes>,
+    //对比的数据(没有打开对比文件时为空)
+    compare: HashMap<char, Strokes>,
+    compare_canvas: HtmlCanvasElement,
+    compare_context: CanvasRenderingContext2d,
+    //用笔输入的点的宽度, 按坐标保存(移动、删除笔画后不需要同步)
+    widths: HashMap<char, HashMap<(u16, u16), u8>>,
+    //每个字的审核信息
+    reviews: Reviews,
+    //加载或者保存到服务器时的审核信息
+    base_reviews: Reviews,
+    //数据中保存的异体字关系(创建异体字时记录)
+    variants: Variants,
+    //加载或者保存到服务器时的异体字关系
+    base_variants: Variants,
+    //打开的所有数据, 正在编辑的数据保存在上面的字段中
+    datasets: Vec<dataset::Dataset>,
+    //正在编辑的数据的序号
+    dataset: usize,
 }
 
 thread_local!{
@@ -77,41 +148,102 @@ thread_local!{
                 .unwrap()
             },
             point: None,
-            chars: vec![],
-            strokes: HashMap::new()
+            pressure: None,
+            view: View::default(),
+            pixel_ratio: 1.0,
+            draw_mode: false,
+            trace: vec![],
+            pointers: vec![],
+            drag: None,
+            chars: CharIndex::new(),
+            strokes: HashMap::new(),
+            base: HashMap::new(),
+            compare: HashMap::new(),
+            compare_canvas: {
+                let canvas = document.get_element_by_id("canvas_compare").unwrap();
+                canvas.dyn_into::<HtmlCanvasElement>()
+                    .map_err(|_| ())
+                    .unwrap()
+            },
+            compare_context: {
+                let canvas = document.get_element_by_id("canvas_compare").unwrap();
+                let canvas: HtmlCanvasElement = canvas
+                    .dyn_into::<HtmlCanvasElement>()
+                    .map_err(|_| ())
+                    .unwrap();
+                canvas
+                .get_context("2d")
+                .unwrap()
+                .unwrap()
+                .dyn_into::<CanvasRenderingContext2d>()
+                .unwrap()
+            },
+            widths: HashMap::new(),
+            reviews: Reviews::new(),
+            base_reviews: Reviews::new(),
+            variants: Variants::default(),
+            base_variants: Variants::default(),
+            datasets: vec![],
+            dataset: 0,
         }
     });
 }
 
 fn start() -> Result<JsValue, JsValue> {
     log("start.");
+    APP_DATA.with(|app_data|{
+        let mut app_data = app_data.borrow_mut();
+        resize_canvas(&mut app_data);
+        let (width, height) = canvas_size(&app_data);
+        app_data.view.fit((0.0, 0.0, GLYPH_SIZE, GLYPH_SIZE), width, height, 0.0);
+    });
     APP_DATA.with(|app_data| -> Result<JsValue, JsValue> {
         let app_data = app_data.borrow();
-        //设置字体
-        app_data.context.set_font("800px 楷体_GB2312");
-        app_data.context.set_stroke_style(&JsValue::from_str("#000"));
-        app_data.context.set_line_width(6.0);
-        
-        //点击设置替换点
-        let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
+
+        //画布大小或者devicePixelRatio改变时重新设置画布像素大小
+        let on_resize = Closure::wrap(Box::new(move |_entries: JsValue| {
             APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
                 let mut app_data = app_data.borrow_mut();
-                let x = event.offset_x() * 2;
-                let y = event.offset_y() * 2;
-                app_data.point = Some((x, y));
+                if !resize_canvas(&mut app_data){
+                    return Ok(JsValue::FALSE);
+                }
                 let ch = app_data.select.value();
-                draw_ch(&*app_data, ch, false, false)
+                draw_ch(&app_data, ch, false, false)
             }).expect("字符绘制失败");
         }) as Box<dyn FnMut(_)>);
-        app_data.canvas.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+        ResizeObserver::new(on_resize.as_ref().unchecked_ref())?.observe(&app_data.canvas);
+        web_sys::window().unwrap().add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref())?;
+        on_resize.forget();
+        
+        //画布的指针和滚轮操作
+        input::bind(&app_data.canvas)?;
+        //快捷键
+        keymap::bind()?;
+        //离开页面前确认
+        autosave::bind();
+        //对比和合并
+        compare::bind()?;
+        //打开本地文件
+        dataset::bind()?;
+        //审核信息和筛选
+        annotation::bind(&app_data)?;
+        filter::bind(&app_data)?;
+        //文本覆盖率
+        corpus::bind()?;
+        //书写练习
+        practice::bind()?;
+        //重复的字
+        similar::bind()?;
+        //异体字
+        linked::bind()?;
+        dataset::fill_datasets(&app_data)?;
         
         //点击切换字符
         let on_select_change = Closure::wrap(Box::new(move |_e: HtmlSelectElement| {
             APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
                 let app_data = app_data.borrow();
                 let ch = app_data.select.value();
-                draw_ch(&*app_data, ch, true, true)
+                draw_ch(&app_data, ch, true, true)
             }).expect("字符绘制失败");
         }) as Box<dyn FnMut(_)>);
         app_data.select.set_onchange(Some(on_select_change.as_ref().unchecked_ref()));
@@ -122,7 +254,7 @@ fn start() -> Result<JsValue, JsValue> {
             APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
                 let app_data = app_data.borrow();
                 let ch = app_data.select.value();
-                draw_ch(&*app_data, ch, false, true)
+                draw_ch(&app_data, ch, false, true)
             }).expect("字符绘制失败");
         }) as Box<dyn FnMut(_)>);
         app_data.select_strokes.set_onchange(Some(on_select_stroke_change.as_ref().unchecked_ref()));
@@ -133,38 +265,22 @@ fn start() -> Result<JsValue, JsValue> {
             APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
                 let app_data = app_data.borrow();
                 let ch = app_data.select.value();
-                draw_ch(&*app_data, ch, false, false)
+                draw_ch(&app_data, ch, false, false)
             }).expect("字符绘制失败");
         }) as Box<dyn FnMut(_)>);
         app_data.select_points.set_onchange(Some(on_select_points_change.as_ref().unchecked_ref()));
         on_select_points_change.forget();
         
         //添加所有字符
-        for ch in app_data.chars.iter() {
-            let option = app_data.document.create_element("option")?;
-            option.set_text_content(Some(&format!("{}", ch)));
-            app_data.select.append_child(&option)?;
-        }
+        fill_chars(&app_data)?;
         app_data.select.set_selected_index(0);
-        draw_ch(&*app_data, app_data.select.value(), true, true)?;
+        draw_ch(&app_data, app_data.select.value(), true, true)?;
         app_data.select.set_value(&app_data.select.value());
 
         //搜索
         let on_search_change = Closure::wrap(Box::new(move |_e: HtmlSelectElement| {
-            APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
-                let app_data = app_data.borrow();
-                let ch = app_data.search.value();
-                if ch.len() == 0 {
-                    return Ok(JsValue::FALSE);
-                }
-                if let Ok(idx) = app_data.chars.binary_search(&ch.chars().next().unwrap()){
-                    app_data.select.set_selected_index(idx as i32);
-                    draw_ch(&*app_data, ch, true, true)?;
-                }else{
-                    alert("没有这个字!");
-                }
-                Ok(JsValue::TRUE)
-            }).expect("字符搜索失败");
+            let text = APP_DATA.with(|app_data| app_data.borrow().search.value());
+            search::search(&text).expect("字符搜索失败");
         }) as Box<dyn FnMut(_)>);
         app_data.search.set_onchange(Some(on_search_change.as_ref().unchecked_ref()));
         on_search_change.forget();
@@ -178,37 +294,41 @@ fn start() -> Result<JsValue, JsValue> {
             //添加字符
             let ch = app_data.add.value();
             let ch = ch.trim();
-            if ch.len() == 0 {
+            if ch.is_empty() {
                 alert("请输入字符!");
                 return Ok(JsValue::FALSE);
             }
-            let chr = ch.chars().nth(0).unwrap();
+            let chr = ch.chars().next().unwrap();
 
-            let idx = if let Ok(idx) = app_data.chars.binary_search(&chr){
+            if app_data.chars.insert(chr).is_none(){
                 alert("字符已存在!");
-                idx
             }else{
-                app_data.chars.push(chr);
                 app_data.strokes.insert(chr,  vec![vec![(50,50)]]);
 
                 //添加所有字符
-                app_data.select.set_text_content(None);
-                for ch in app_data.chars.iter() {
-                    let option = app_data.document.create_element("option")?;
-                    option.set_text_content(Some(&format!("{}", ch)));
-                    app_data.select.append_child(&option)?;
-                }
-
-                app_data.chars.len()-1
-            };
+                fill_chars(&app_data)?;
+                modified(&app_data, chr);
+            }
             //选择对应的字符
-            app_data.select.set_selected_index(idx as i32);
-            draw_ch(&*app_data, ch.to_string(), true, true)
+            select_char(&app_data, chr)?;
+            draw_ch(&app_data, ch.to_string(), true, true)
         }).expect("字符绘制失败");
     }) as Box<dyn FnMut(_)>);
     get_element_by_id("btn_add").set_onclick(Some(btn_add_click.as_ref().unchecked_ref()));
     btn_add_click.forget();
 
+    let btn_remove_char_click = Closure::wrap(Box::new(move || {
+        remove_char().expect("remove_char调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_remove_char").set_onclick(Some(btn_remove_char_click.as_ref().unchecked_ref()));
+    btn_remove_char_click.forget();
+
+    let btn_rename_char_click = Closure::wrap(Box::new(move || {
+        rename_char().expect("rename_char调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_rename_char").set_onclick(Some(btn_rename_char_click.as_ref().unchecked_ref()));
+    btn_rename_char_click.forget();
+
     let btn_replace_click = Closure::wrap(Box::new(move |_e: HtmlElement| {
         change_point(0).expect("change_point调用失败");
     }) as Box<dyn FnMut(_)>);
@@ -237,11 +357,14 @@ fn start() -> Result<JsValue, JsValue> {
         APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
             let mut app_data = app_data.borrow_mut();
             //添加一笔
-            let ch = app_data.select.value();
-            let key = ch.chars().next().unwrap();
+            let key = match current_char(&app_data){
+                Some(ch) => ch,
+                None => return Ok(JsValue::FALSE)
+            };
             let strokes = app_data.strokes.get_mut(&key).unwrap();
             strokes.push(vec![(50,50)]);
-            draw_ch(&*app_data, ch, true, true)
+            modified(&app_data, key);
+            draw_ch(&app_data, key.to_string(), true, true)
         }).expect("add_stroke调用失败");
     }) as Box<dyn FnMut()>);
     get_element_by_id("btn_add_stroke").set_onclick(Some(btn_add_stroke_click.as_ref().unchecked_ref()));
@@ -251,11 +374,14 @@ fn start() -> Result<JsValue, JsValue> {
         APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
             let mut app_data = app_data.borrow_mut();
             //删除一笔
-            let ch = app_data.select.value().chars().next().unwrap();
-            let select_index = app_data.select_strokes.selected_index() as usize;
+            let (ch, select_index) = match current_char(&app_data).and_then(|ch| Some((ch, selected_stroke(&app_data, ch)?))){
+                Some(selected) => selected,
+                None => return Ok(JsValue::FALSE)
+            };
             let strokes = app_data.strokes.get_mut(&ch).unwrap();
             strokes.remove(select_index);
-            draw_ch(&*app_data, format!("{}", ch), true, true)
+            modified(&app_data, ch);
+            draw_ch(&app_data, format!("{}", ch), true, true)
         }).expect("delete_stroke调用失败");
     }) as Box<dyn FnMut()>);
     get_element_by_id("btn_delete_stroke").set_onclick(Some(btn_delete_stroke_click.as_ref().unchecked_ref()));
@@ -300,6 +426,43 @@ fn start() -> Result<JsValue, JsValue> {
     get_element_by_id("btn_move_backward5").set_onclick(Some(btn_move_backward5_click.as_ref().unchecked_ref()));
     btn_move_backward5_click.forget();
 
+    let btn_draw_mode_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| {
+            let mut app_data = app_data.borrow_mut();
+            //切换描画笔画模式
+            app_data.draw_mode = !app_data.draw_mode;
+            app_data.trace.clear();
+            let text = if app_data.draw_mode { "结束描画" } else { "描画笔画" };
+            get_element_by_id("btn_draw_mode").set_inner_text(text);
+        });
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_draw_mode").set_onclick(Some(btn_draw_mode_click.as_ref().unchecked_ref()));
+    btn_draw_mode_click.forget();
+
+    let btn_fit_view_click = Closure::wrap(Box::new(move || {
+        fit_view(false).expect("fit_view调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_fit_view").set_onclick(Some(btn_fit_view_click.as_ref().unchecked_ref()));
+    btn_fit_view_click.forget();
+
+    let btn_reset_view_click = Closure::wrap(Box::new(move || {
+        fit_view(true).expect("reset_view调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_reset_view").set_onclick(Some(btn_reset_view_click.as_ref().unchecked_ref()));
+    btn_reset_view_click.forget();
+
+    let btn_save_click = Closure::wrap(Box::new(move || {
+        save(false).expect("save调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_save").set_onclick(Some(btn_save_click.as_ref().unchecked_ref()));
+    btn_save_click.forget();
+
+    let btn_save_as_click = Closure::wrap(Box::new(move || {
+        save(true).expect("save调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_save_as").set_onclick(Some(btn_save_as_click.as_ref().unchecked_ref()));
+    btn_save_as_click.forget();
+
     let gen_vec_click = Closure::wrap(Box::new(move || {
         gen_vec().expect("gen_vec调用失败");
     }) as Box<dyn FnMut()>);
@@ -312,9 +475,238 @@ fn start() -> Result<JsValue, JsValue> {
     get_element_by_id("gen_map").set_onclick(Some(gen_map_click.as_ref().unchecked_ref()));
     gen_map_click.forget();
 
+    let gen_patch_click = Closure::wrap(Box::new(move || {
+        gen_patch().expect("gen_patch调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("gen_patch").set_onclick(Some(gen_patch_click.as_ref().unchecked_ref()));
+    gen_patch_click.forget();
+
+    //选择补丁文件并应用
+    bind_open_file("btn_apply_patch", "patch_file", apply_patch);
+
+    let gen_widths_click = Closure::wrap(Box::new(move || {
+        gen_widths().expect("gen_widths调用失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("gen_widths").set_onclick(Some(gen_widths_click.as_ref().unchecked_ref()));
+    gen_widths_click.forget();
+
     Ok(JsValue::TRUE)
 }
 
+//字符相对于加载时的修改状态, 用作字符列表中选项的class
+fn change_class(app_data:&AppData, ch: char) -> &'static str{
+    match (app_data.base.get(&ch), app_data.strokes.get(&ch)){
+        (None, Some(_)) => "added",
+        (Some(old), Some(strokes)) if old != strokes => "modified",
+        _ => ""
+    }
+}
+
+//字符列表中选项的样式: 修改状态和审核状态
+fn option_class(app_data:&AppData, ch: char) -> String{
+    let status = app_data.reviews.get(&ch).map(|review| review.status).unwrap_or_default();
+    format!("{} {}", change_class(app_data, ch), status.id()).trim().to_string()
+}
+
+//字符列表中一个字的选项(HTML)
+fn char_option(app_data:&AppData, ch: char) -> String{
+    let text = match ch{
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '&' => "&amp;".to_string(),
+        '"' => "&quot;".to_string(),
+        _ => ch.to_string()
+    };
+    format!("<option value=\"{0}\" class=\"{1}\">{0}</option>", text, option_class(app_data, ch))
+}
+
+//重新生成字符列表, 只显示符合筛选条件的字(一次设置全部选项, 避免逐个添加元素)
+fn fill_chars(app_data:&AppData) -> Result<(), JsValue>{
+    let chars = filter::Filter::current().apply(app_data);
+    let html: String = chars.iter().map(|ch| char_option(app_data, *ch)).collect();
+    app_data.select.set_inner_html(&html);
+    filter::show_count(app_data, chars.len());
+    linked::reset();
+    for id in EDIT_BUTTONS.iter(){
+        if chars.is_empty(){
+            get_element_by_id(id).set_attribute("disabled", "")?;
+        }else{
+            get_element_by_id(id).remove_attribute("disabled")?;
+        }
+    }
+    Ok(())
+}
+
+//字符列表中选择的字(列表为空时为None)
+fn current_char(app_data:&AppData) -> Option<char>{
+    app_data.select.value().chars().next().filter(|ch| app_data.strokes.contains_key(ch))
+}
+
+//当前选择的笔画的序号(字没有笔画时为None)
+fn selected_stroke(app_data:&AppData, ch: char) -> Option<usize>{
+    let count = app_data.strokes.get(&ch).map(|strokes| strokes.len()).unwrap_or(0);
+    let index = app_data.select_strokes.selected_index();
+    if index >= 0 && (index as usize) < count{
+        Some(index as usize)
+    }else{
+        None
+    }
+}
+
+//在字符列表中选择ch, 被筛选掉时清除筛选条件
+fn select_char(app_data:&AppData, ch: char) -> Result<(), JsValue>{
+    let value = ch.to_string();
+    app_data.select.set_value(&value);
+    if app_data.select.value() != value{
+        filter::reset();
+        fill_chars(app_data)?;
+        app_data.select.set_value(&value);
+    }
+    Ok(())
+}
+
+//删除一个字(笔画、笔画宽度和审核信息), 不存在时返回false
+fn delete_char(app_data:&mut AppData, ch: char) -> bool{
+    if app_data.chars.remove(ch).is_none(){
+        return false;
+    }
+    app_data.strokes.remove(&ch);
+    app_data.widths.remove(&ch);
+    app_data.reviews.remove(&ch);
+    api::notify("removed", Some(ch));
+    download::clear();
+    autosave::modified(&dataset::current_name(app_data));
+    true
+}
+
+//把from改为to, 笔画、笔画宽度和审核信息一起移动
+fn move_char(app_data:&mut AppData, from: char, to: char) -> Result<(), String>{
+    app_data.chars.rename(from, to)?;
+    if let Some(strokes) = app_data.strokes.remove(&from){
+        app_data.strokes.insert(to, strokes);
+    }
+    if let Some(widths) = app_data.widths.remove(&from){
+        app_data.widths.insert(to, widths);
+    }
+    if let Some(review) = app_data.reviews.remove(&from){
+        app_data.reviews.insert(to, review);
+    }
+    api::notify("removed", Some(from));
+    Ok(())
+}
+
+//删除当前的字, 删除后选择列表中的下一个字
+fn remove_char() -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let ch = match app_data.select.value().chars().next(){
+            Some(ch) => ch,
+            None => return Ok(JsValue::FALSE)
+        };
+        if !web_sys::window().unwrap().confirm_with_message(&format!("确定删除\"{}\"吗?", ch))?{
+            return Ok(JsValue::FALSE);
+        }
+        let index = app_data.select.selected_index();
+        delete_char(&mut app_data, ch);
+        fill_chars(&app_data)?;
+        //删除了最后一个字时列表为空, draw_ch会清空画布
+        if app_data.select.length() > 0{
+            app_data.select.set_selected_index(index.min(app_data.select.length() as i32 - 1));
+        }
+        draw_ch(&app_data, app_data.select.value(), true, true)
+    })
+}
+
+//把当前的字改为输入框中的字
+fn rename_char() -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let from = match app_data.select.value().chars().next(){
+            Some(ch) => ch,
+            None => return Ok(JsValue::FALSE)
+        };
+        let to = match app_data.add.value().trim().chars().next(){
+            Some(ch) => ch,
+            None => {
+                alert("请输入新的字符!");
+                return Ok(JsValue::FALSE);
+            }
+        };
+        if from == to{
+            return Ok(JsValue::FALSE);
+        }
+        if let Err(err) = move_char(&mut app_data, from, to){
+            alert(&err);
+            return Ok(JsValue::FALSE);
+        }
+        fill_chars(&app_data)?;
+        select_char(&app_data, to)?;
+        modified(&app_data, to);
+        draw_ch(&app_data, to.to_string(), true, true)
+    })
+}
+
+//字符被修改: 更新字符列表中的标记, 清除已经过时的下载链接, 稍后自动保存
+fn modified(app_data:&AppData, ch: char){
+    let value = ch.to_string();
+    let option = (0..app_data.select.length()).filter_map(|i| app_data.select.item(i))
+        .find(|option| option.get_attribute("value").as_deref() == Some(&value));
+    if let Some(option) = option{
+        option.set_class_name(&option_class(app_data, ch));
+    }
+    api::notify("modified", Some(ch));
+    download::clear();
+    autosave::modified(&dataset::current_name(app_data));
+}
+
+//点击按钮选择文件, 读取文件内容后交给callback处理
+fn bind_open_file(button:&str, input:&str, callback: fn(Vec<u8>) -> Result<JsValue, JsValue>){
+    let input = get_element_by_id(input).dyn_into::<HtmlInputElement>().unwrap();
+    let file_input = input.clone();
+    let on_button_click = Closure::wrap(Box::new(move || {
+        file_input.click();
+    }) as Box<dyn FnMut()>);
+    get_element_by_id(button).set_onclick(Some(on_button_click.as_ref().unchecked_ref()));
+    on_button_click.forget();
+
+    let file_input = input.clone();
+    let on_file_change = Closure::wrap(Box::new(move || {
+        if let Some(file) = file_input.files().and_then(|files| files.get(0)){
+            wasm_bindgen_futures::spawn_local(async move{
+                let data = match JsFuture::from(file.array_buffer()).await{
+                    Ok(buffer) => Uint8Array::new(&buffer).to_vec(),
+                    Err(_) => {
+                        alert("文件读取失败!");
+                        return;
+                    }
+                };
+                callback(data).expect("文件处理失败");
+            });
+        }
+        file_input.set_value("");
+    }) as Box<dyn FnMut()>);
+    input.set_onchange(Some(on_file_change.as_ref().unchecked_ref()));
+    on_file_change.forget();
+}
+
+//加载页面相对路径的文件
+async fn fetch_data(url:&str) -> Result<Vec<u8>, JsValue>{
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+
+    let request = Request::new_with_str_and_init(url, &opts)?;
+    let window = web_sys::window().unwrap();
+    let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
+
+    let resp: Response = resp_value.dyn_into().unwrap();
+    if !resp.ok(){
+        return Err(JsValue::from_str(&format!("{}加载失败:{}", url, resp.status())));
+    }
+    let buffer = JsFuture::from(resp.array_buffer()?).await?;
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
+
 fn get_element_by_id(id:&str) -> HtmlElement{
     web_sys::window().unwrap().document().unwrap()
                 .get_element_by_id(id).unwrap()
@@ -323,18 +715,58 @@ fn get_element_by_id(id:&str) -> HtmlElement{
                 .unwrap()
 }
 
+//画布的CSS像素大小
+fn canvas_size(app_data:&AppData) -> (f64, f64){
+    (app_data.canvas.width() as f64 / app_data.pixel_ratio, app_data.canvas.height() as f64 / app_data.pixel_ratio)
+}
+
+//按照显示大小和devicePixelRatio设置画布像素大小, 视图跟随缩放, 字形坐标保持不变
+fn resize_canvas(app_data:&mut AppData) -> bool{
+    let rect = app_data.canvas.get_bounding_client_rect();
+    let pixel_ratio = web_sys::window().unwrap().device_pixel_ratio();
+    let (width, height) = ((rect.width() * pixel_ratio).round() as u32, (rect.height() * pixel_ratio).round() as u32);
+    if width == 0 || height == 0{
+        return false;
+    }
+    if width == app_data.canvas.width() && height == app_data.canvas.height() && pixel_ratio == app_data.pixel_ratio{
+        return false;
+    }
+    let old_size = canvas_size(app_data);
+    app_data.pixel_ratio = pixel_ratio;
+    app_data.canvas.set_width(width);
+    app_data.canvas.set_height(height);
+    let new_size = canvas_size(app_data);
+    app_data.view.resize(old_size, new_size);
+    true
+}
+
 fn draw_ch(app_data:&AppData, ch: String, reset_strokes: bool, reset_points: bool) -> Result<JsValue, JsValue> {
+    //字符列表为空(被筛选为空或者删除了所有的字)时清空画布和笔画列表
+    if ch.is_empty(){
+        app_data.search.set_value("");
+        app_data.context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        app_data.context.clear_rect(0.0, 0.0, app_data.canvas.width() as f64, app_data.canvas.height() as f64);
+        app_data.select_strokes.set_text_content(None);
+        app_data.select_points.set_text_content(None);
+        return Ok(JsValue::FALSE);
+    }
     app_data.search.set_value(&ch);
-    app_data.context.set_fill_style(&JsValue::from_str("#777"));
     //let ch = SELECT.value().unwrap();
     let (width, height) = (app_data.canvas.width() as f64, app_data.canvas.height() as f64);
+    let view = app_data.view;
+    app_data.context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
     app_data.context.clear_rect(0.0, 0.0, width, height);
-    app_data.context.fill_text(&ch, width * 0.1, height * 0.75)?;
-
+    //之后都使用字形坐标绘制
+    let (scale, ratio) = (view.scale * app_data.pixel_ratio, app_data.pixel_ratio);
+    app_data.context.set_transform(scale, 0.0, 0.0, scale, view.offset_x * ratio, view.offset_y * ratio)?;
     let key = ch.chars().next().unwrap();
+    //练习时不显示参考字体
+    draw_reference(&app_data.context, view.scale, key, dataset::current_script(app_data).of(key), !practice::active())?;
 
-    let map = &app_data.strokes;
-    let strokes = map.get(&key).unwrap();
+    let strokes = match app_data.strokes.get(&key){
+        Some(strokes) => strokes,
+        None => return Ok(JsValue::FALSE)
+    };
     if reset_strokes {
         //创建笔画数据
         app_data.select_strokes.set_text_content(None);
@@ -344,75 +776,189 @@ fn draw_ch(app_data:&AppData, ch: String, reset_strokes: bool, reset_points: boo
             app_data.select_strokes.append_child(&option)?;
         }
         app_data.select_strokes.set_selected_index(0);
+        //切换了字, 显示审核信息
+        annotation::show(app_data, key);
     }
 
     if reset_points{
         //清空对应的所有点
         app_data.select_points.set_text_content(None);
-        let idx = app_data.select_strokes.selected_index() as usize;
-        for point in strokes[idx].iter() {
+        let widths = app_data.widths.get(&key);
+        //删除了所有笔画时没有点
+        let points = selected_stroke(app_data, key).map(|idx| strokes[idx].as_slice()).unwrap_or(&[]);
+        for point in points.iter() {
             let option = app_data.document.create_element("option")?;
-            option.set_text_content(Some(&format!("({},{})", point.0, point.1)));
+            match widths.and_then(|widths| widths.get(point)){
+                Some(width) => option.set_text_content(Some(&format!("({},{}) 宽{}", point.0, point.1, width))),
+                None => option.set_text_content(Some(&format!("({},{})", point.0, point.1)))
+            }
             app_data.select_points.append_child(&option)?;
         }
         app_data.select_points.set_selected_index(0);
     }
 
+    //练习时只显示写过的笔画
+    if practice::active(){
+        practice::draw(app_data, key)?;
+        return draw_trace(app_data);
+    }
+
+    //字所属的文字和不符合规则的地方
+    let script = dataset::current_script(app_data).of(key);
+    let problems = script::lint(script, strokes);
+    get_element_by_id("lint_info").set_inner_text(&format!("{} {}", script.name(), problems.join("; ")));
+
     //绘制所有笔画
     draw_strokes(app_data, key);
 
     //绘制笔画当前选择的点
-    let idx = app_data.select_strokes.selected_index() as usize;
-    let pt = strokes[idx][app_data.select_strokes.selected_index() as usize];
-    app_data.context.set_fill_style(&JsValue::from_str("#f00"));
-    app_data.context.begin_path();
-    app_data.context.arc(pt.0 as f64, pt.1 as f64, 20.0, 0.0, 360.0)?;
-    app_data.context.fill();
+    if let Some(points) = selected_stroke(app_data, key).map(|idx| &strokes[idx]).filter(|points| !points.is_empty()){
+        let pt = points[selected_point(app_data, points)];
+        app_data.context.set_fill_style_str("#f00");
+        app_data.context.begin_path();
+        app_data.context.arc(pt.0 as f64, pt.1 as f64, 10.0 / view.scale, 0.0, 360.0)?;
+        app_data.context.fill();
+    }
 
     //绘制用户点击的点
     if let Some(point) = app_data.point.as_ref() {
-        app_data.context.set_fill_style(&JsValue::from_str("rgba(0, 0, 255, 0.5)"));
+        app_data.context.set_fill_style_str("rgba(0, 0, 255, 0.5)");
         app_data.context.begin_path();
-        app_data.context.arc(point.0 as f64, point.1 as f64, 20.0, 0.0, 360.0)?;
+        app_data.context.arc(point.0 as f64, point.1 as f64, 10.0 / view.scale, 0.0, 360.0)?;
         app_data.context.fill();
 
-        app_data.context.set_fill_style(&JsValue::from_str("rgba(255, 255, 0, 0.5)"));
+        app_data.context.set_fill_style_str("rgba(255, 255, 0, 0.5)");
         app_data.context.begin_path();
-        app_data.context.arc(point.0 as f64, point.1 as f64, 10.0, 0.0, 360.0)?;
+        app_data.context.arc(point.0 as f64, point.1 as f64, 5.0 / view.scale, 0.0, 360.0)?;
         app_data.context.fill();
     }
 
+    draw_trace(app_data)?;
+
+    //对比的数据
+    compare::draw(app_data, key)?;
+    //异体字
+    linked::draw(app_data, key)?;
+
     Ok(JsValue::TRUE)
 }
+//字形区域边框、书写格和参考字体中的字(按字所属文字选择字体和基线)
+fn draw_reference(context:&CanvasRenderingContext2d, scale: f64, ch: char, script: Script, show_text: bool) -> Result<(), JsValue>{
+    //设置字体(改变画布大小后需要重新设置)
+    context.set_font(&format!("800px {}", script.font()));
+    context.set_stroke_style_str("#ccc");
+    context.set_line_width(1.0 / scale);
+    context.stroke_rect(0.0, 0.0, GLYPH_SIZE, GLYPH_SIZE);
+    context.set_stroke_style_str("#eee");
+    for y in script.guides(){
+        context.begin_path();
+        context.move_to(0.0, *y);
+        context.line_to(GLYPH_SIZE, *y);
+        context.stroke();
+    }
+    if show_text{
+        context.set_fill_style_str("#777");
+        context.set_text_align("center");
+        context.fill_text(&ch.to_string(), GLYPH_SIZE * 0.5, script.baseline())?;
+    }
+    Ok(())
+}
+
+//正在描画的路径
+fn draw_trace(app_data:&AppData) -> Result<JsValue, JsValue>{
+    if app_data.trace.len() > 1 {
+        app_data.context.set_stroke_style_str("#00f");
+        app_data.context.begin_path();
+        app_data.context.move_to(app_data.trace[0].0, app_data.trace[0].1);
+        for point in app_data.trace.iter().skip(1) {
+            app_data.context.line_to(point.0, point.1);
+        }
+        app_data.context.stroke();
+    }
+    Ok(JsValue::TRUE)
+}
+
 
 fn draw_strokes(app_data:&AppData, ch: char) {
     let map = &app_data.strokes;
     let strokes = map.get(&ch).unwrap();
     let select_stroke = app_data.select_strokes.selected_index() as usize;
+    //线宽不随缩放变化
+    app_data.context.set_line_width(3.0 / app_data.view.scale);
     for (i, stroke) in strokes.iter().enumerate() {
         //当前笔画红色
         if i == select_stroke {
-            app_data.context.set_stroke_style(&JsValue::from_str("#f00"));
+            app_data.context.set_stroke_style_str("#f00");
         } else {
-            app_data.context.set_stroke_style(&JsValue::from_str("#000"));
+            app_data.context.set_stroke_style_str("#000");
         }
+        let start = match stroke.first(){
+            Some(start) => start,
+            None => continue
+        };
         app_data.context.begin_path();
-        app_data.context.move_to(stroke[0].0 as f64, stroke[0].1 as f64);
-        for i in 1..stroke.len() {
-            app_data.context.line_to(stroke[i].0 as f64, stroke[i].1 as f64);
+        app_data.context.move_to(start.0 as f64, start.1 as f64);
+        for point in stroke.iter().skip(1) {
+            app_data.context.line_to(point.0 as f64, point.1 as f64);
         }
         app_data.context.stroke();
     }
 }
 
+//适应字形(reset为true时显示整个字形区域)
+fn fit_view(reset: bool) -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let key = match current_char(&app_data){
+            Some(ch) => ch,
+            None => return Ok(JsValue::FALSE)
+        };
+        let (width, height) = canvas_size(&app_data);
+        let (rect, margin) = if reset{
+            (Some((0.0, 0.0, GLYPH_SIZE, GLYPH_SIZE)), 0.0)
+        }else{
+            (bounding_box(&app_data.strokes[&key]), FIT_MARGIN)
+        };
+        if let Some(rect) = rect{
+            app_data.view.fit(rect, width, height, margin);
+        }
+        draw_ch(&app_data, key.to_string(), false, false)
+    })
+}
+
+//在当前笔画后面插入一笔(新添加的字只有默认的一个点时替换它)
+fn insert_stroke(app_data:&mut AppData, stroke: Vec<(u16, u16)>) -> Result<JsValue, JsValue>{
+    let key = match current_char(app_data){
+        Some(ch) => ch,
+        None => return Ok(JsValue::FALSE)
+    };
+    let ch = key.to_string();
+    let select_index = app_data.select_strokes.selected_index().max(0) as usize;
+    let strokes = app_data.strokes.get_mut(&key).unwrap();
+    let index = if strokes.len() == 1 && strokes[0].len() == 1{
+        strokes[0] = stroke;
+        0
+    }else{
+        let index = (select_index + 1).min(strokes.len());
+        strokes.insert(index, stroke);
+        index
+    };
+    modified(app_data, key);
+    draw_ch(app_data, ch.clone(), true, true)?;
+    //选中新的笔画
+    app_data.select_strokes.set_selected_index(index as i32);
+    draw_ch(app_data, ch, false, true)
+}
+
 fn chagne_stroke(op:i32, val:Option<i32>) -> Result<JsValue, JsValue>{
-    hide_download();
     APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
         let mut app_data = app_data.borrow_mut();
         //替换当前字符
-        let ch = app_data.select.value().chars().next().unwrap();
+        let (ch, select_index) = match current_char(&app_data).and_then(|ch| Some((ch, selected_stroke(&app_data, ch)?))){
+            Some(selected) => selected,
+            None => return Ok(JsValue::FALSE)
+        };
         //获取所有笔画
-        let select_index = app_data.select_strokes.selected_index() as usize;
         let strokes = app_data.strokes.get_mut(&ch).unwrap();
 
         if op==0{
@@ -422,7 +968,8 @@ fn chagne_stroke(op:i32, val:Option<i32>) -> Result<JsValue, JsValue>{
                 strokes[select_index-1] = strokes[select_index].clone();
                 strokes[select_index] = before;
                 app_data.select_strokes.set_selected_index(select_index as i32-1);
-                draw_ch(&*app_data, app_data.select.value(), false, true)?;
+                modified(&app_data, ch);
+                draw_ch(&app_data, app_data.select.value(), false, true)?;
             }else{
                 alert("已经是第一笔了!");
             }
@@ -433,7 +980,8 @@ fn chagne_stroke(op:i32, val:Option<i32>) -> Result<JsValue, JsValue>{
                 strokes[select_index+1] = strokes[select_index].clone();
                 strokes[select_index] = after;
                 app_data.select_strokes.set_selected_index(select_index as i32+1);
-                draw_ch(&*app_data, app_data.select.value(), false, true)?;
+                modified(&app_data, ch);
+                draw_ch(&app_data, app_data.select.value(), false, true)?;
             }else{
                 alert("已经到最后一笔了!");
             }
@@ -468,7 +1016,8 @@ fn chagne_stroke(op:i32, val:Option<i32>) -> Result<JsValue, JsValue>{
             }else{
                 app_data.select_strokes.set_selected_index(select_index as i32+1);
             }
-            draw_ch(&*app_data, app_data.select.value(), false, true)?;
+            modified(&app_data, ch);
+            draw_ch(&app_data, app_data.select.value(), false, true)?;
         }
 
         Ok(JsValue::TRUE)
@@ -476,21 +1025,24 @@ fn chagne_stroke(op:i32, val:Option<i32>) -> Result<JsValue, JsValue>{
 }
 
 fn change_point(op:i32) -> Result<JsValue, JsValue>{
-    hide_download();
     APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
         let mut app_data = app_data.borrow_mut();
         let point = app_data.point;
+        let pressure = app_data.pressure;
         //替换当前字符
-        let ch = app_data.select.value().chars().next().unwrap();
+        let (ch, select_index) = match current_char(&app_data).and_then(|ch| Some((ch, selected_stroke(&app_data, ch)?))){
+            Some(selected) => selected,
+            None => return Ok(JsValue::FALSE)
+        };
         //获取所有笔画
-        let select_index = app_data.select_strokes.selected_index() as usize;
+        let select_point = app_data.select_points.selected_index().max(0) as usize;
         let strokes = app_data.strokes.get_mut(&ch).unwrap();
         //获取选择的笔画
         let points:&mut Vec<(u16, u16)> = &mut strokes[select_index];
-        if points.len() == 0{
+        if points.is_empty(){
             return Ok(JsValue::FALSE);
         }
-        let mut point_index = select_index;
+        let mut point_index = select_point.min(points.len() - 1);
         if op==3{
             //只有一个点不删除
             if points.len()==1{
@@ -517,63 +1069,238 @@ fn change_point(op:i32) -> Result<JsValue, JsValue>{
                 }   
             }
         }
+        //用笔输入的点记录宽度
+        if let (Some(point), Some(pressure), true) = (point, pressure, op != 3){
+            let width = pressure_to_width(pressure);
+            app_data.widths.entry(ch).or_insert_with(HashMap::new).insert((point.0 as u16, point.1 as u16), width);
+        }
+        if op == 3 || point.is_some(){
+            modified(&app_data, ch);
+        }
         let ch = app_data.select.value();
-        draw_ch(&*app_data, ch, false, true)?;
+        draw_ch(&app_data, ch, false, true)?;
         //选中编辑的点
         let ch = app_data.select.value();
         app_data.select_points.set_selected_index(point_index as i32);
-        draw_ch(&*app_data, ch, false, false)
+        draw_ch(&app_data, ch, false, false)
     })
 }
 
+//当前选择的点在笔画中的位置
+fn selected_point(app_data:&AppData, points:&[(u16, u16)]) -> usize{
+    (app_data.select_points.selected_index().max(0) as usize).min(points.len().saturating_sub(1))
+}
+
+//移动当前选择的点
+fn nudge_point(dx: i32, dy: i32) -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let (key, select_index) = match current_char(&app_data).and_then(|ch| Some((ch, selected_stroke(&app_data, ch)?))){
+            Some(selected) => selected,
+            None => return Ok(JsValue::FALSE)
+        };
+        if app_data.strokes[&key][select_index].is_empty(){
+            return Ok(JsValue::FALSE);
+        }
+        let ch = key.to_string();
+        let point_index = selected_point(&app_data, &app_data.strokes[&key][select_index]);
+        let point = &mut app_data.strokes.get_mut(&key).unwrap()[select_index][point_index];
+        point.0 = (point.0 as i32 + dx).clamp(0, GLYPH_SIZE as i32) as u16;
+        point.1 = (point.1 as i32 + dy).clamp(0, GLYPH_SIZE as i32) as u16;
+        modified(&app_data, key);
+        draw_ch(&app_data, ch.clone(), false, true)?;
+        app_data.select_points.set_selected_index(point_index as i32);
+        draw_ch(&app_data, ch, false, false)
+    })
+}
+
+//保存到磁盘上的文件(Ctrl+S), 浏览器不支持File System Access时下载STROKES.data
+fn save(save_as: bool) -> Result<JsValue, JsValue>{
+    let (name, data) = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        (dataset::current_name(&app_data), strokes_data(&app_data))
+    });
+    //通过stroke-server打开时只把修改的字发送到服务器
+    if !save_as && server::available(&name){
+        return save_to_server();
+    }
+    wasm_bindgen_futures::spawn_local(async move{
+        match disk::save(&name, &data, save_as).await{
+            Ok(disk::Saved::Written(file_name)) => {
+                download::clear();
+                autosave::saved(&name);
+                log(&format!("已保存到{}", file_name));
+            }
+            Ok(disk::Saved::Cancelled) => (),
+            Ok(disk::Saved::Unsupported) => {
+                gen_vec().expect("gen_vec调用失败");
+            }
+            Err(err) => {
+                log(&format!("{:?}", err));
+                alert("保存失败!");
+            }
+        }
+    });
+    Ok(JsValue::TRUE)
+}
+
+//把修改的字保存到stroke-server
+fn save_to_server() -> Result<JsValue, JsValue>{
+    let (name, patch, reviews, variants) = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        (dataset::current_name(&app_data), Patch::diff(&app_data.base, &app_data.strokes, &app_data.chars),
+            review::changed(&app_data.base_reviews, &app_data.reviews), app_data.variants.added(&app_data.base_variants))
+    });
+    if patch.changes.is_empty() && reviews.is_empty() && variants.is_empty(){
+        autosave::saved(&name);
+        return Ok(JsValue::FALSE);
+    }
+    let data = write_patch(&patch, &reviews, &variants);
+    wasm_bindgen_futures::spawn_local(async move{
+        match server::save_patch(&data).await{
+            Ok(server::Saved::Written(message)) => {
+                log(&message);
+                APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+                    let mut app_data = app_data.borrow_mut();
+                    //服务器上的数据已经更新, 作为新的原始数据
+                    app_data.base = app_data.strokes.clone();
+                    app_data.base_reviews = app_data.reviews.clone();
+                    app_data.base_variants = app_data.variants.clone();
+                    let current = app_data.select.value();
+                    fill_chars(&app_data)?;
+                    app_data.select.set_value(&current);
+                    Ok(JsValue::TRUE)
+                }).expect("保存失败");
+                autosave::saved(&name);
+            }
+            Ok(server::Saved::Cancelled) => (),
+            Err(err) => alert(&format!("保存到服务器失败: {}", err.as_string().unwrap_or_default()))
+        }
+    });
+    Ok(JsValue::TRUE)
+}
+
 //生成map数据
 fn gen_map() -> Result<JsValue, JsValue> {
     APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
         let app_data = app_data.borrow();
         //序列化
         let data: Vec<u8> = serialize(&app_data.strokes).unwrap();
-        let link_em = get_element_by_id("download_button");
-        link_em.set_attribute("download", "gb2312.data")?;
-        let link = link_em.dyn_into::<HtmlAnchorElement>()
-                    .map_err(|_| ())
-                    .unwrap();
-        link.set_href(&format!(r#"data:application/octet-stream;base64,{}"#, encode(&data)));
-        link.set_inner_text("gb2312.data");
+        //文件名按数据中最多的文字(汉字为gb2312.data)
+        let name = dataset::current_script(&app_data).main().map_file();
+        download::download(&name, &name, &data)?;
         Ok(JsValue::TRUE)
     })
 }
 
+//按照字符顺序排列的所有字
+fn current_list(app_data:&AppData) -> StrokeList{
+    let mut vec:StrokeList = vec![];
+
+    for ch in app_data.chars.iter(){
+        vec.push((*ch, app_data.strokes.get(ch).unwrap().clone()));
+    }
+
+    vec
+}
+
+//按照字符顺序序列化为Vec<(char, Vec<Vec<(u16, u16)>>)>, 后面附加审核信息和异体字关系
+fn strokes_data(app_data:&AppData) -> Vec<u8>{
+    let mut data = write_list(&current_list(app_data));
+    append_reviews(&mut data, &app_data.reviews);
+    append_variants(&mut data, &app_data.variants);
+    data
+}
+
 //生成数组
 fn gen_vec() -> Result<JsValue, JsValue> {
     APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
         let app_data = app_data.borrow();
         //序列化
-        let mut vec:Vec<(char, Vec<Vec<(u16, u16)>>)> = vec![];
+        let data: Vec<u8> = strokes_data(&app_data);
+        download::download("STROKES.data", "STROKES.data(替换页面中STROKES.data)", &data)?;
+        Ok(JsValue::TRUE)
+    })
+}
+
+//生成补丁, 只包含加载之后新增、修改、删除的字
+fn gen_patch() -> Result<JsValue, JsValue> {
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let app_data = app_data.borrow();
+        let patch = Patch::diff(&app_data.base, &app_data.strokes, &app_data.chars);
+        let (added, modified, removed) = patch.counts();
+        //序列化
+        let data: Vec<u8> = write_patch(&patch, &review::changed(&app_data.base_reviews, &app_data.reviews), &app_data.variants.added(&app_data.base_variants));
+        download::download("STROKES.patch", &format!("STROKES.patch(新增{}, 修改{}, 删除{})", added, modified, removed), &data)?;
+        Ok(JsValue::TRUE)
+    })
+}
+
+//读取补丁文件并应用到当前数据, 返回(新增, 修改, 删除)的字数, 出错时不修改数据(不弹出对话框, 脚本接口也使用)
+fn patch_data(data:&[u8]) -> Result<(usize, usize, usize), JsValue>{
+    let (patch, reviews, variants) = read_patch(data).map_err(|_| JsValue::from_str("补丁文件格式错误"))?;
+    APP_DATA.with(|app_data| -> Result<(), JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        let mut data = current_list(&app_data);
+        patch.apply(&mut data).map_err(|err| JsValue::from_str(&format!("补丁应用失败: {}", err)))?;
+        download::clear();
+        let current = app_data.select.value();
+        app_data.chars = data.iter().map(|(ch, _)| *ch).collect();
+        app_data.strokes = data.into_iter().collect();
+        review::apply_changes(&mut app_data.reviews, &reviews);
+        app_data.variants.merge(&variants);
+        fill_chars(&app_data)?;
+        //保持原来选择的字
+        app_data.select.set_value(&current);
+        if app_data.select.value() != current{
+            app_data.select.set_selected_index(0);
+        }
+        api::notify("reload", None);
+        autosave::modified(&dataset::current_name(&app_data));
+        draw_ch(&app_data, app_data.select.value(), true, true)?;
+        Ok(())
+    })?;
+    Ok(patch.counts())
+}
 
-        for ch in app_data.chars.iter(){
-            vec.push((*ch, app_data.strokes.get(ch).unwrap().clone()));
+//选择的补丁文件
+fn apply_patch(data: Vec<u8>) -> Result<JsValue, JsValue>{
+    match patch_data(&data){
+        Ok((added, modified, removed)) => {
+            alert(&format!("已应用补丁: 新增{}, 修改{}, 删除{}", added, modified, removed));
+            Ok(JsValue::TRUE)
         }
+        Err(err) => {
+            alert(&format!("{}!", err.as_string().unwrap_or_default()));
+            Ok(JsValue::FALSE)
+        }
+    }
+}
 
-        let data: Vec<u8> = serialize(&vec).unwrap();
-        let link_em = get_element_by_id("download_button");
-        link_em.set_attribute("download", "STROKES.data")?;
-        let link = link_em.dyn_into::<HtmlAnchorElement>()
-                    .map_err(|_| ())
-                    .unwrap();
-        link.set_href(&format!(r#"data:application/octet-stream;base64,{}"#, encode(&data)));
-        link.set_inner_text("STROKES.data(替换页面中STROKES.data)");
+//笔的压力转换为笔画宽度
+fn pressure_to_width(pressure: f32) -> u8{
+    (pressure.clamp(0.0, 1.0) * MAX_WIDTH).round().max(1.0) as u8
+}
+
+//生成笔画宽度数据HashMap<char, Vec<Vec<u8>>>, 与笔画中的点一一对应, 0表示没有宽度
+fn gen_widths() -> Result<JsValue, JsValue> {
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let app_data = app_data.borrow();
+        let mut map: HashMap<char, Vec<Vec<u8>>> = HashMap::new();
+        for (ch, widths) in app_data.widths.iter(){
+            if let Some(strokes) = app_data.strokes.get(ch){
+                map.insert(*ch, strokes.iter().map(|stroke| {
+                    stroke.iter().map(|point| *widths.get(point).unwrap_or(&0)).collect()
+                }).collect());
+            }
+        }
+        //序列化
+        let data: Vec<u8> = serialize(&map).unwrap();
+        download::download("WIDTHS.data", "WIDTHS.data", &data)?;
         Ok(JsValue::TRUE)
     })
 }
 
-fn hide_download(){
-    let link_em = get_element_by_id("download_button");
-    let link = link_em.dyn_into::<HtmlAnchorElement>()
-                .map_err(|_| ())
-                .unwrap();
-    link.set_href("");
-    link.set_inner_text("");
-}
 
 #[wasm_bindgen]
 extern "C" {
@@ -585,67 +1312,17 @@ extern "C" {
 #[wasm_bindgen]
 pub async fn run() -> Result<JsValue, JsValue> {
     //加载文件
-    let mut opts = RequestInit::new();
-    opts.method("GET");
-    opts.mode(RequestMode::Cors);
-
-    let request = Request::new_with_str_and_init("STROKES.data", &opts)?;
-    let window = web_sys::window().unwrap();
-    let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
-
-    let resp: Response = resp_value.dyn_into().unwrap();
-    let buffer = JsFuture::from(resp.array_buffer()?).await?;
-    let data:Vec<u8> = Uint8Array::new(&buffer).to_vec();
-    let strokes: Vec<(char, Vec<Vec<(u16, u16)>>)> = deserialize(&data).unwrap();
-    log(&format!("字符个数{:?}", strokes.len()));
-
-    let mut count_map = HashMap::new();
-
-    let mut chars = vec![];
-    for (ch, _strokes) in &strokes {
-        chars.push(*ch);
-        if count_map.contains_key(ch){
-            *count_map.get_mut(ch).unwrap() += 1;
-        }else{
-            count_map.insert(*ch, 1);
-        }
-    }
-
-    //检查是否有不存在的字
-    //let articls: HashMap<String, String> = deserialize(&ARTICLS).unwrap();
-    // let poems: HashMap<String, String> = deserialize(&POEMS).unwrap();
-
-    // for (_title, content) in &poems {
-    //     for ch in content.chars(){
-    //         match ch {
-    //             '—' => continue,
-    //             '。' => continue,
-    //             '，' => continue,
-    //             '；' => continue,
-    //             '”' => continue,
-    //             '“' => continue,
-    //             '‘' => continue,
-    //             '’' => continue,
-    //             '！' => continue,
-    //             '：' => continue,
-    //             '？' => continue,
-    //             '!'|'0'|'1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'|']'|'['|'、'|','|':'|'…'|'·'|'《'|'》'|'?'|'`'|'"'|' '|'［'|'］' => continue,
-    //             _ => (),
-    //         }
-    //         if !chars.contains(&ch){
-    //             js!(console.log("没有文字:", @{format!("{}", ch)}));
-    //         }
-    //     }
-    // }
+    let name = dataset::url_dataset();
+    let data = fetch_data(&name).await?;
+    let (loaded, restored) = dataset::load(&name, &data).await?;
+    //通过stroke-server打开时可以直接保存到服务器
+    server::detect().await;
 
     APP_DATA.with(|app_data|{
-        let mut app_data = app_data.borrow_mut();
-        app_data.chars = chars;
-        let mut map = HashMap::new();
-        for (ch, strokes) in strokes {
-            map.insert(ch, strokes);
-        }
-        app_data.strokes = map;
+        dataset::add(&mut app_data.borrow_mut(), loaded);
     });
+    if restored{
+        autosave::modified(&name);
+    }
     start()
 }
\ No newline at end of file
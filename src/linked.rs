@@ -0,0 +1,156 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlSelectElement};
+use crate::{APP_DATA, AppData, alert, compare, current_char, draw_ch, fetch_data, fill_chars, get_element_by_id, log, modified, select_char};
+use crate::variant::{Kind, Variants};
+
+//Unihan数据库的异体字表, 放在页面目录中
+const VARIANTS_FILE: &str = "Unihan_Variants.txt";
+
+thread_local!{
+    //Unihan数据库中的关系, 数据中保存的关系在AppData中
+    static VARIANTS: RefCell<Option<Rc<Variants>>> = const { RefCell::new(None) };
+    //异体字列表对应的字
+    static SHOWN: Cell<Option<char>> = const { Cell::new(None) };
+}
+
+fn select_variant() -> HtmlSelectElement{
+    get_element_by_id("select_variant").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+//异体字列表中选择的字
+fn selected() -> Option<char>{
+    select_variant().value().chars().next()
+}
+
+//字符列表变化(切换数据、新建或删除字)后重新填充异体字列表, 避免"(无)"的标记过时
+pub fn reset(){
+    SHOWN.with(|shown| shown.set(None));
+}
+
+//ch的异体字: 数据中保存的关系和Unihan数据库中的关系
+fn links(app_data:&AppData, ch: char) -> Vec<(char, Kind)>{
+    let mut links = Variants::default();
+    let unihan = VARIANTS.with(|variants| variants.borrow().as_ref().map(|variants| variants.get(ch))).unwrap_or_default();
+    for (variant, kind) in app_data.variants.get(ch).into_iter().chain(unihan){
+        links.link(ch, variant, kind);
+    }
+    links.get(ch)
+}
+
+//后台加载异体字表, 没有时只显示数据中保存的异体字
+pub fn bind() -> Result<JsValue, JsValue>{
+    spawn_local(async{
+        let variants = match fetch_data(VARIANTS_FILE).await{
+            Ok(data) => {
+                let mut variants = Variants::default();
+                variants.add_unihan(&String::from_utf8_lossy(&data));
+                variants
+            }
+            Err(_) => {
+                log(&format!("没有{}", VARIANTS_FILE));
+                return;
+            }
+        };
+        VARIANTS.with(|current| *current.borrow_mut() = Some(Rc::new(variants)));
+        reset();
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            match current_char(&app_data){
+                Some(ch) => draw(&app_data, ch),
+                None => Ok(JsValue::FALSE)
+            }
+        }).expect("异体字绘制失败");
+    });
+
+    let on_variant_change = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            match SHOWN.with(|shown| shown.get()){
+                Some(ch) => draw(&app_data, ch),
+                None => Ok(JsValue::FALSE)
+            }
+        }).expect("异体字绘制失败");
+    }) as Box<dyn FnMut()>);
+    select_variant().set_onchange(Some(on_variant_change.as_ref().unchecked_ref()));
+    on_variant_change.forget();
+
+    //跳转到选择的异体字, 数据中没有时用当前字的笔画创建
+    let btn_goto_variant_click = Closure::wrap(Box::new(move || {
+        goto_variant().expect("异体字跳转失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_goto_variant").set_onclick(Some(btn_goto_variant_click.as_ref().unchecked_ref()));
+    btn_goto_variant_click.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+fn goto_variant() -> Result<JsValue, JsValue>{
+    let (from, to) = match (SHOWN.with(|shown| shown.get()), selected()){
+        (Some(from), Some(to)) => (from, to),
+        _ => return Ok(JsValue::FALSE)
+    };
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let mut app_data = app_data.borrow_mut();
+        if !app_data.strokes.contains_key(&to){
+            if !web_sys::window().unwrap().confirm_with_message(&format!("数据中没有\"{}\", 是否用\"{}\"的笔画创建?", to, from))?{
+                return Ok(JsValue::FALSE);
+            }
+            let strokes = match app_data.strokes.get(&from){
+                Some(strokes) => strokes.clone(),
+                None => {
+                    alert(&format!("数据中没有\"{}\"!", from));
+                    return Ok(JsValue::FALSE);
+                }
+            };
+            //异体字关系和新建的字一起保存在数据中
+            if let Some((_, kind)) = links(&app_data, from).into_iter().find(|(variant, _)| *variant == to){
+                app_data.variants.link(from, to, kind);
+            }
+            app_data.chars.insert(to);
+            app_data.strokes.insert(to, strokes);
+            fill_chars(&app_data)?;
+            modified(&app_data, to);
+        }
+        select_char(&app_data, to)?;
+        draw_ch(&app_data, to.to_string(), true, true)
+    })
+}
+
+//填充ch的异体字列表
+fn fill(app_data:&AppData, ch: char) -> Result<(), JsValue>{
+    let select = select_variant();
+    select.set_text_content(None);
+    for (variant, kind) in links(app_data, ch){
+        let option = app_data.document.create_element("option")?;
+        option.set_attribute("value", &variant.to_string())?;
+        let missing = if app_data.strokes.contains_key(&variant) { "" } else { "(无)" };
+        option.set_text_content(Some(&format!("{} {}{}", kind.name(), variant, missing)));
+        select.append_child(&option)?;
+    }
+    SHOWN.with(|shown| shown.set(Some(ch)));
+    Ok(())
+}
+
+//在编辑画布旁边用相同的视图显示ch的异体字
+pub fn draw(app_data:&AppData, ch: char) -> Result<JsValue, JsValue>{
+    if SHOWN.with(|shown| shown.get()) != Some(ch){
+        fill(app_data, ch)?;
+    }
+    let container = get_element_by_id("variant");
+    let variant = match selected(){
+        Some(variant) => variant,
+        None => {
+            container.style().set_property("display", "none")?;
+            return Ok(JsValue::FALSE);
+        }
+    };
+    container.style().set_property("display", "inline-block")?;
+    let canvas = get_element_by_id("canvas_variant").dyn_into::<HtmlCanvasElement>()?;
+    let context = canvas.get_context("2d")?.unwrap().dyn_into::<CanvasRenderingContext2d>()?;
+    get_element_by_id("btn_goto_variant").set_inner_text(if app_data.strokes.contains_key(&variant) { "跳转" } else { "用当前的字创建" });
+    compare::draw_glyph(app_data, &canvas, &context, variant, app_data.strokes.get(&variant), "#000")
+}
@@ -0,0 +1,286 @@
+use std::f64::consts::PI;
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use crate::{APP_DATA, AppData, draw_ch, get_element_by_id};
+use crate::data::Strokes;
+use crate::matching::match_stroke;
+
+//同一笔写错这么多次后显示提示
+const HINT_AFTER: u32 = 2;
+
+thread_local!{
+    //正在练习的字(没有练习时为None)
+    static PRACTICE: RefCell<Option<Practice>> = const { RefCell::new(None) };
+}
+
+//是否正在练习, 练习时画布上写的笔画交给submit
+pub fn active() -> bool{
+    PRACTICE.with(|practice| practice.borrow().is_some())
+}
+
+//练习一个字: 按笔顺逐笔书写, 和数据中的笔画比较
+pub struct Practice{
+    ch: char,
+    expected: Strokes,
+    //已经写对的笔画数
+    done: usize,
+    //每一笔写错的次数
+    mistakes: Vec<u32>,
+    hint: bool,
+    message: String,
+}
+
+impl Practice{
+    pub fn new(ch: char, expected: Strokes) -> Practice{
+        let count = expected.len();
+        Practice{
+            ch,
+            expected,
+            done: 0,
+            mistakes: vec![0; count],
+            hint: false,
+            message: if count == 0 { "没有笔画数据".to_string() } else { format!("请写第1笔(共{}笔)", count) },
+        }
+    }
+
+    pub fn finished(&self) -> bool{
+        self.done >= self.expected.len()
+    }
+
+    //得分(0~100): 每一笔第一次写对得满分, 写错n次得1/(n+1)
+    pub fn score(&self) -> u32{
+        if self.expected.is_empty(){
+            return 0;
+        }
+        let total: f64 = self.mistakes.iter().map(|n| 1.0 / (*n as f64 + 1.0)).sum();
+        (total / self.expected.len() as f64 * 100.0).round() as u32
+    }
+
+    //写了一笔
+    pub fn submit(&mut self, drawn:&[(f64, f64)]){
+        if self.finished(){
+            return;
+        }
+        let index = self.done;
+        let result = match_stroke(drawn, &self.expected[index]);
+        if result.accepted(){
+            self.done += 1;
+            self.hint = false;
+            self.message = if self.finished(){
+                format!("完成! 得分{}", self.score())
+            }else{
+                format!("第{}笔正确, 请写第{}笔", index + 1, index + 2)
+            };
+            return;
+        }
+        self.mistakes[index] += 1;
+        //和后面的笔画相同时是笔顺错误
+        let other = (index + 1..self.expected.len()).find(|i| match_stroke(drawn, &self.expected[*i]).accepted());
+        self.message = if let Some(other) = other{
+            format!("笔顺错误: 这是第{}笔, 应该先写第{}笔", other + 1, index + 1)
+        }else if result.direction <= 0.0{
+            format!("第{}笔的方向反了", index + 1)
+        }else if result.start_error >= result.distance.max(result.end_error){
+            format!("第{}笔的起点不对", index + 1)
+        }else{
+            format!("第{}笔的形状不对", index + 1)
+        };
+        if self.mistakes[index] >= HINT_AFTER{
+            self.hint = true;
+        }
+    }
+
+    pub fn show_hint(&mut self){
+        if !self.finished(){
+            self.hint = true;
+        }
+    }
+}
+
+pub fn bind() -> Result<JsValue, JsValue>{
+    //开始或者结束练习
+    let btn_practice_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let mut app_data = app_data.borrow_mut();
+            if active(){
+                PRACTICE.with(|practice| *practice.borrow_mut() = None);
+            }else{
+                app_data.draw_mode = false;
+                app_data.trace.clear();
+                get_element_by_id("btn_draw_mode").set_inner_text("描画笔画");
+                start(&app_data);
+            }
+            show()?;
+            draw_ch(&app_data, app_data.select.value(), false, false)
+        }).expect("练习失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_practice").set_onclick(Some(btn_practice_click.as_ref().unchecked_ref()));
+    btn_practice_click.forget();
+
+    let btn_practice_hint_click = Closure::wrap(Box::new(move || {
+        PRACTICE.with(|practice| {
+            if let Some(practice) = practice.borrow_mut().as_mut(){
+                practice.show_hint();
+            }
+        });
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            draw_ch(&app_data, app_data.select.value(), false, false)
+        }).expect("提示失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_practice_hint").set_onclick(Some(btn_practice_hint_click.as_ref().unchecked_ref()));
+    btn_practice_hint_click.forget();
+
+    let btn_practice_restart_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            start(&app_data);
+            show()?;
+            draw_ch(&app_data, app_data.select.value(), false, false)
+        }).expect("重新练习失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_practice_restart").set_onclick(Some(btn_practice_restart_click.as_ref().unchecked_ref()));
+    btn_practice_restart_click.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+//练习当前选择的字
+fn start(app_data:&AppData){
+    let ch = match app_data.select.value().chars().next(){
+        Some(ch) => ch,
+        None => return
+    };
+    let expected = app_data.strokes.get(&ch).cloned().unwrap_or_default();
+    PRACTICE.with(|practice| *practice.borrow_mut() = Some(Practice::new(ch, expected)));
+}
+
+//显示练习的按钮和提示信息
+fn show() -> Result<(), JsValue>{
+    let message = PRACTICE.with(|practice| practice.borrow().as_ref().map(|practice| practice.message.clone()));
+    get_element_by_id("practice").style().set_property("display", if message.is_some() { "" } else { "none" })?;
+    get_element_by_id("btn_practice").set_inner_text(if message.is_some() { "结束练习" } else { "练习" });
+    get_element_by_id("practice_info").set_inner_text(&message.unwrap_or_default());
+    Ok(())
+}
+
+//写完一笔
+pub fn submit(app_data:&AppData, drawn:&[(f64, f64)]) -> Result<JsValue, JsValue>{
+    PRACTICE.with(|practice| {
+        if let Some(practice) = practice.borrow_mut().as_mut(){
+            practice.submit(drawn);
+        }
+    });
+    show()?;
+    draw_ch(app_data, app_data.select.value(), false, false)
+}
+
+//练习时的画布: 不显示没有写的笔画, 提示时用灰色显示下一笔
+pub fn draw(app_data:&AppData, ch: char) -> Result<JsValue, JsValue>{
+    //切换了字, 重新开始练习
+    if PRACTICE.with(|practice| practice.borrow().as_ref().map(|practice| practice.ch)) != Some(ch){
+        start(app_data);
+        show()?;
+    }
+    PRACTICE.with(|practice| -> Result<JsValue, JsValue>{
+        let practice = practice.borrow();
+        let practice = match practice.as_ref(){
+            Some(practice) => practice,
+            None => return Ok(JsValue::FALSE)
+        };
+        let (context, scale) = (&app_data.context, app_data.view.scale);
+        context.set_line_width(3.0 / scale);
+        let hint = if practice.hint { practice.expected.get(practice.done) } else { None };
+        let strokes = practice.expected.iter().take(practice.done).map(|stroke| ("#000", stroke))
+            .chain(hint.map(|stroke| ("#bbb", stroke)));
+        for (color, stroke) in strokes{
+            let start = match stroke.first(){
+                Some(start) => start,
+                None => continue
+            };
+            context.set_stroke_style_str(color);
+            context.begin_path();
+            context.move_to(start.0 as f64, start.1 as f64);
+            for point in stroke.iter().skip(1){
+                context.line_to(point.0 as f64, point.1 as f64);
+            }
+            context.stroke();
+        }
+        //提示的笔画标出起点
+        if let Some(start) = hint.and_then(|stroke| stroke.first()){
+            context.set_fill_style_str("#0a0");
+            context.begin_path();
+            context.arc(start.0 as f64, start.1 as f64, 12.0 / scale, 0.0, 2.0 * PI)?;
+            context.fill();
+        }
+        Ok(JsValue::TRUE)
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    //"十": 先横后竖
+    fn practice() -> Practice{
+        Practice::new('十', vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]])
+    }
+
+    const HORIZONTAL: [(f64, f64); 2] = [(110.0, 505.0), (890.0, 495.0)];
+    const VERTICAL: [(f64, f64); 2] = [(505.0, 110.0), (495.0, 890.0)];
+
+    #[test]
+    fn correct_order_scores_full(){
+        let mut practice = practice();
+        assert_eq!(practice.message, "请写第1笔(共2笔)");
+        practice.submit(&HORIZONTAL);
+        assert_eq!((practice.done, practice.message.as_str()), (1, "第1笔正确, 请写第2笔"));
+        practice.submit(&VERTICAL);
+        assert!(practice.finished());
+        assert_eq!(practice.score(), 100);
+        assert_eq!(practice.message, "完成! 得分100");
+        //完成后写的笔画不计算
+        practice.submit(&HORIZONTAL);
+        assert_eq!(practice.score(), 100);
+    }
+
+    #[test]
+    fn swapped_strokes_are_order_errors(){
+        let mut practice = practice();
+        practice.submit(&VERTICAL);
+        assert_eq!(practice.done, 0);
+        assert_eq!(practice.message, "笔顺错误: 这是第2笔, 应该先写第1笔");
+        practice.submit(&HORIZONTAL);
+        practice.submit(&VERTICAL);
+        assert!(practice.finished());
+        //第1笔写错一次得一半
+        assert_eq!(practice.score(), 75);
+    }
+
+    #[test]
+    fn reversed_direction_is_reported(){
+        let mut practice = practice();
+        let mut reversed = HORIZONTAL.to_vec();
+        reversed.reverse();
+        practice.submit(&reversed);
+        assert_eq!(practice.done, 0);
+        assert_eq!(practice.message, "第1笔的方向反了");
+        assert!(!practice.hint);
+        //写错HINT_AFTER次后显示提示, 写对后不再提示
+        practice.submit(&reversed);
+        assert!(practice.hint);
+        practice.submit(&HORIZONTAL);
+        assert!(!practice.hint);
+        assert_eq!(practice.mistakes, vec![2, 0]);
+    }
+
+    #[test]
+    fn empty_char_is_finished(){
+        let mut practice = Practice::new('一', vec![]);
+        assert!(practice.finished());
+        assert_eq!((practice.score(), practice.message.as_str()), (0, "没有笔画数据"));
+        practice.show_hint();
+        assert!(!practice.hint);
+    }
+}
@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use crate::{APP_DATA, alert, draw_ch, fetch_data, fill_chars, filter, log, select_char};
+use crate::coverage::is_text_char;
+use crate::dataset::current_script;
+use crate::script::Script;
+use crate::reading::{Readings, is_pinyin_query, parse_radical_query};
+
+//读音和部首表, 放在页面目录中, 没有的文件会被忽略
+const UNIHAN_FILES: [&str; 3] = ["Unihan_Readings.txt", "Unihan_IRGSources.txt", "Unihan_RadicalStrokeCounts.txt"];
+const CEDICT_FILE: &str = "cedict_ts.u8";
+
+thread_local!{
+    //第一次按拼音或部首查找时加载
+    static READINGS: RefCell<Option<Rc<Readings>>> = const { RefCell::new(None) };
+}
+
+async fn readings() -> Rc<Readings>{
+    if let Some(readings) = READINGS.with(|readings| readings.borrow().clone()){
+        return readings;
+    }
+    let mut readings = Readings::default();
+    for file in UNIHAN_FILES.iter(){
+        match fetch_data(file).await{
+            Ok(data) => readings.add_unihan(&String::from_utf8_lossy(&data)),
+            Err(_) => log(&format!("没有{}", file))
+        }
+    }
+    match fetch_data(CEDICT_FILE).await{
+        Ok(data) => readings.add_cedict(&String::from_utf8_lossy(&data)),
+        Err(_) => log(&format!("没有{}", CEDICT_FILE))
+    }
+    let readings = Rc::new(readings);
+    READINGS.with(|cache| *cache.borrow_mut() = Some(readings.clone()));
+    readings
+}
+
+//把找到的字放到队列中显示
+fn show_queue(label: String, chars: Vec<char>) -> Result<JsValue, JsValue>{
+    APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+        let app_data = app_data.borrow();
+        let chars: Vec<char> = chars.into_iter().filter(|ch| app_data.strokes.contains_key(ch)).collect();
+        let first = match chars.first(){
+            Some(ch) => *ch,
+            None => {
+                alert(&format!("没有找到: {}", label));
+                return Ok(JsValue::FALSE);
+            }
+        };
+        filter::set_queue(Some((label, chars)));
+        fill_chars(&app_data)?;
+        select_char(&app_data, first)?;
+        draw_ch(&app_data, first.to_string(), true, true)
+    })
+}
+
+//按部首或拼音查找
+fn search_readings(query: String, radical: bool){
+    spawn_local(async move{
+        let readings = readings().await;
+        let result = if !radical{
+            if !readings.has_pinyin(){
+                alert(&format!("按拼音查找需要把{}或者{}放到页面目录中!", UNIHAN_FILES[0], CEDICT_FILE));
+                return;
+            }
+            show_queue(format!("拼音{}", query), readings.by_pinyin(&query))
+        }else{
+            if !readings.has_radicals(){
+                alert(&format!("按部首查找需要把{}放到页面目录中!", UNIHAN_FILES[1]));
+                return;
+            }
+            let (radical, residual) = parse_radical_query(&query).unwrap();
+            let label = match residual{
+                Some(residual) => format!("部首{}+{}", radical, residual),
+                None => format!("部首{}", radical)
+            };
+            show_queue(label, readings.by_radical(radical, residual))
+        };
+        result.expect("查找失败");
+    });
+}
+
+//搜索框: 一个字直接跳转; 拼音(shui、shui3、shuǐ)或部首("85"、"85.4"、"水+4")查找; 多个字放到队列中逐个检查
+//拉丁字母的数据不按拼音和部首查找, 输入的字母就是要检查的字
+pub fn search(text:&str) -> Result<JsValue, JsValue>{
+    let text = text.trim();
+    let chars: Vec<char> = text.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if chars.is_empty(){
+        return Ok(JsValue::FALSE);
+    }
+    let (script, exists) = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        (current_script(&app_data), chars.len() == 1 && app_data.strokes.contains_key(&chars[0]))
+    });
+    //数据中有的字直接跳转(例如拉丁字母a)
+    if exists{
+        return APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            select_char(&app_data, chars[0])?;
+            draw_ch(&app_data, chars[0].to_string(), true, true)
+        });
+    }
+    if script.main() != Script::Latin{
+        //部首后面必须有数字, 否则是查找这个字
+        if parse_radical_query(text).is_some() && (chars[0].is_ascii_digit() || chars.iter().skip(1).any(|ch| ch.is_ascii_digit())){
+            search_readings(text.to_string(), true);
+            return Ok(JsValue::TRUE);
+        }
+        if is_pinyin_query(text){
+            search_readings(text.to_string(), false);
+            return Ok(JsValue::TRUE);
+        }
+    }
+    if chars.len() == 1{
+        alert("没有这个字!");
+        return Ok(JsValue::FALSE);
+    }
+    //粘贴的文本: 去掉重复的字和标点
+    let mut seen = HashSet::new();
+    let queue: Vec<char> = chars.into_iter().filter(|ch| is_text_char(*ch, &script) && seen.insert(*ch)).collect();
+    let missing: String = APP_DATA.with(|app_data| {
+        let app_data = app_data.borrow();
+        queue.iter().filter(|ch| !app_data.strokes.contains_key(ch)).collect()
+    });
+    if !missing.is_empty(){
+        alert(&format!("数据中没有这些字: {}", missing));
+    }
+    let label = format!("文本{}个字", queue.len());
+    show_queue(label, queue)
+}
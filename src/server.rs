@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use js_sys::{JSON, Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response};
+
+//保存令牌的localStorage键
+const TOKEN_KEY: &str = "server_token";
+
+thread_local!{
+    //stroke-server提供的数据文件名, 不是通过stroke-server打开页面时为None
+    static DATA_NAME: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+//保存的结果
+pub enum Saved{
+    //服务器返回的说明
+    Written(String),
+    //没有输入令牌
+    Cancelled,
+}
+
+async fn send(method:&str, url:&str, body: Option<&[u8]>, token: Option<&str>) -> Result<Response, JsValue>{
+    let opts = RequestInit::new();
+    opts.set_method(method);
+    if let Some(body) = body{
+        opts.set_body(&Uint8Array::from(body));
+    }
+    let request = Request::new_with_str_and_init(url, &opts)?;
+    if let Some(token) = token{
+        request.headers().set("Authorization", &format!("Bearer {}", token))?;
+    }
+    let window = web_sys::window().unwrap();
+    JsFuture::from(window.fetch_with_request(&request)).await?.dyn_into()
+}
+
+//检查页面是不是由stroke-server提供的
+pub async fn detect(){
+    let name = async{
+        let response = send("GET", "api/status", None, None).await?;
+        if !response.ok(){
+            return Ok(None);
+        }
+        let status = JSON::parse(&JsFuture::from(response.text()?).await?.as_string().unwrap_or_default())?;
+        Ok::<_, JsValue>(Reflect::get(&status, &"data".into())?.as_string())
+    }.await.unwrap_or(None);
+    DATA_NAME.with(|data_name| *data_name.borrow_mut() = name);
+}
+
+//数据是否可以保存到服务器
+pub fn available(name:&str) -> bool{
+    DATA_NAME.with(|data_name| data_name.borrow().as_deref() == Some(name))
+}
+
+fn storage() -> Option<web_sys::Storage>{
+    web_sys::window().unwrap().local_storage().ok().flatten()
+}
+
+//读取保存的令牌, 没有时让用户输入
+fn token() -> Result<Option<String>, JsValue>{
+    if let Some(token) = storage().and_then(|storage| storage.get_item(TOKEN_KEY).ok().flatten()){
+        return Ok(Some(token));
+    }
+    let token = web_sys::window().unwrap().prompt_with_message("请输入stroke-server启动时显示的令牌:")?;
+    let token = token.map(|token| token.trim().to_string()).filter(|token| !token.is_empty());
+    if let (Some(storage), Some(token)) = (storage(), token.as_ref()){
+        storage.set_item(TOKEN_KEY, token)?;
+    }
+    Ok(token)
+}
+
+//把补丁(STROKES.patch格式)发送到服务器
+pub async fn save_patch(patch:&[u8]) -> Result<Saved, JsValue>{
+    let token = match token()?{
+        Some(token) => token,
+        None => return Ok(Saved::Cancelled)
+    };
+    let response = send("PATCH", "api/data", Some(patch), Some(&token)).await?;
+    let text = JsFuture::from(response.text()?).await?.as_string().unwrap_or_default();
+    match response.status(){
+        200 => Ok(Saved::Written(text)),
+        401 => {
+            //令牌错误, 下次重新输入
+            if let Some(storage) = storage(){
+                storage.remove_item(TOKEN_KEY)?;
+            }
+            Err(JsValue::from_str("令牌错误"))
+        }
+        //其他人已经保存过这些字
+        409 => Err(JsValue::from_str(&format!("{}, 请重新加载页面后再修改", text))),
+        status => Err(JsValue::from_str(&format!("{} {}", status, text)))
+    }
+}
@@ -0,0 +1,164 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlSelectElement};
+use crate::{APP_DATA, AppData, current_list, draw_ch, fill_chars, filter, get_element_by_id, log, select_char};
+use crate::duplicate::{Bitmap, NEAR_THRESHOLD, StrokeInk, duplicates};
+use crate::dataset::current_script;
+use crate::script::Script;
+use crate::view::GLYPH_SIZE;
+
+//参考字形点阵的大小
+const BITMAP_SIZE: u32 = 48;
+//笔画和自己的字形吻合程度低于这个值时, 和其他字的字形比较
+const GLYPH_THRESHOLD: f64 = 0.6;
+//和其他字的字形吻合程度至少要高出这么多才列出来
+const GLYPH_MARGIN: f64 = 0.15;
+//最多和其他字形比较的字数(吻合程度最低的)
+const MAX_CHECKED: usize = 100;
+
+pub fn bind() -> Result<JsValue, JsValue>{
+    let btn_duplicates_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            show(&app_data)
+        }).expect("查找重复的字失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_duplicates").set_onclick(Some(btn_duplicates_click.as_ref().unchecked_ref()));
+    btn_duplicates_click.forget();
+
+    //点击列表跳转到对应的字
+    let on_duplicate_change = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            match duplicate_list().value().chars().next().filter(|ch| app_data.strokes.contains_key(ch)){
+                Some(ch) => {
+                    select_char(&app_data, ch)?;
+                    draw_ch(&app_data, ch.to_string(), true, true)
+                }
+                None => Ok(JsValue::FALSE)
+            }
+        }).expect("字符绘制失败");
+    }) as Box<dyn FnMut()>);
+    duplicate_list().set_onchange(Some(on_duplicate_change.as_ref().unchecked_ref()));
+    on_duplicate_change.forget();
+
+    //把列表中的字放到队列中逐个检查
+    let btn_queue_duplicates_click = Closure::wrap(Box::new(move || {
+        APP_DATA.with(|app_data| -> Result<JsValue, JsValue>{
+            let app_data = app_data.borrow();
+            let list = duplicate_list();
+            let mut chars: Vec<char> = vec![];
+            for i in 0..list.length(){
+                let text = list.item(i).and_then(|option| option.get_attribute("data-chars")).unwrap_or_default();
+                for ch in text.chars(){
+                    if !chars.contains(&ch) && app_data.strokes.contains_key(&ch){
+                        chars.push(ch);
+                    }
+                }
+            }
+            let first = match chars.first(){
+                Some(ch) => *ch,
+                None => return Ok(JsValue::FALSE)
+            };
+            filter::set_queue(Some(("重复的字".to_string(), chars)));
+            fill_chars(&app_data)?;
+            select_char(&app_data, first)?;
+            draw_ch(&app_data, first.to_string(), true, true)
+        }).expect("检查失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_queue_duplicates").set_onclick(Some(btn_queue_duplicates_click.as_ref().unchecked_ref()));
+    btn_queue_duplicates_click.forget();
+
+    let btn_close_click = Closure::wrap(Box::new(move || {
+        get_element_by_id("duplicates").style().set_property("display", "none").expect("关闭失败");
+    }) as Box<dyn FnMut()>);
+    get_element_by_id("btn_close_duplicates").set_onclick(Some(btn_close_click.as_ref().unchecked_ref()));
+    btn_close_click.forget();
+
+    Ok(JsValue::TRUE)
+}
+
+fn duplicate_list() -> HtmlSelectElement{
+    get_element_by_id("select_duplicates").dyn_into::<HtmlSelectElement>().unwrap()
+}
+
+//用参考字体把字绘制成点阵
+fn render(context:&CanvasRenderingContext2d, ch: char, script: Script) -> Result<Bitmap, JsValue>{
+    let size = BITMAP_SIZE as f64;
+    let scale = size / GLYPH_SIZE;
+    context.clear_rect(0.0, 0.0, size, size);
+    context.set_font(&format!("{}px {}", 800.0 * scale, script.font()));
+    context.set_text_align("center");
+    context.set_fill_style_str("#000");
+    context.fill_text(&ch.to_string(), size * 0.5, script.baseline() * scale)?;
+    let data = context.get_image_data(0.0, 0.0, size, size)?;
+    Ok(Bitmap::from_rgba(BITMAP_SIZE as usize, &data.data()))
+}
+
+//笔画和参考字形不吻合, 却和另一个字的字形吻合的字: (字, 自己的吻合程度, 另一个字, 吻合程度)
+fn glyph_mismatches(app_data:&AppData) -> Result<Vec<(char, f64, char, f64)>, JsValue>{
+    let canvas = app_data.document.create_element("canvas")?.dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(BITMAP_SIZE);
+    canvas.set_height(BITMAP_SIZE);
+    let context = canvas.get_context("2d")?.unwrap().dyn_into::<CanvasRenderingContext2d>()?;
+    let mut bitmaps = vec![];
+    let script = current_script(app_data);
+    for ch in app_data.chars.iter(){
+        let bitmap = render(&context, *ch, script.of(*ch))?;
+        //字体中没有的字不比较
+        if !bitmap.is_empty(){
+            bitmaps.push((*ch, bitmap));
+        }
+    }
+    let size = BITMAP_SIZE as usize;
+    let mut poor: Vec<(char, f64)> = bitmaps.iter()
+        .map(|(ch, bitmap)| (*ch, StrokeInk::new(&app_data.strokes[ch], size).matches(bitmap)))
+        .filter(|(_, score)| *score < GLYPH_THRESHOLD)
+        .collect();
+    poor.sort_by(|a, b| a.1.total_cmp(&b.1));
+    poor.truncate(MAX_CHECKED);
+    let mut result = vec![];
+    for (ch, own) in poor{
+        //笔画经过的格子只计算一次, 再和所有字形比较
+        let ink = StrokeInk::new(&app_data.strokes[&ch], size);
+        let best = bitmaps.iter().filter(|(other, _)| *other != ch)
+            .map(|(other, bitmap)| (*other, ink.matches(bitmap)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((other, score)) = best.filter(|(_, score)| *score > own + GLYPH_MARGIN){
+            result.push((ch, own, other, score));
+        }
+    }
+    Ok(result)
+}
+
+//查找重复的字并显示列表, 每一项的value是需要检查的字
+fn show(app_data:&AppData) -> Result<JsValue, JsValue>{
+    let list = duplicate_list();
+    list.set_text_content(None);
+    //data-chars是这一项涉及的两个字
+    let add = |a: char, b: char, text: String| -> Result<(), JsValue>{
+        let option = app_data.document.create_element("option")?;
+        option.set_attribute("value", &a.to_string())?;
+        option.set_attribute("data-chars", &format!("{}{}", a, b))?;
+        option.set_text_content(Some(&text));
+        list.append_child(&option)?;
+        Ok(())
+    };
+    let duplicates = duplicates(&current_list(app_data), NEAR_THRESHOLD);
+    for duplicate in &duplicates{
+        let text = if duplicate.identical{
+            format!("{} {} 完全相同", duplicate.a, duplicate.b)
+        }else{
+            format!("{} {} 几乎相同({:.3})", duplicate.a, duplicate.b, duplicate.score)
+        };
+        add(duplicate.a, duplicate.b, text)?;
+    }
+    let mismatches = glyph_mismatches(app_data)?;
+    for (ch, own, other, score) in &mismatches{
+        add(*ch, *other, format!("{} {} 笔画更像\"{}\"的字形({:.2}/{:.2})", ch, other, other, own, score))?;
+    }
+    log(&format!("重复的字{}对, 字形不符{}个", duplicates.len(), mismatches.len()));
+    get_element_by_id("duplicates_info").set_inner_text(&format!("{}对字的笔画相同或几乎相同, {}个字的笔画更像另一个字", duplicates.len(), mismatches.len()));
+    get_element_by_id("duplicates").style().set_property("display", "block")?;
+    Ok(JsValue::TRUE)
+}
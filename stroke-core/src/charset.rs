@@ -0,0 +1,56 @@
+use std::sync::OnceLock;
+
+//GB2312一级汉字(按拼音排列, 3755个)和二级汉字(按部首排列, 3008个)
+const GB2312_1: &str = include_str!("charset/gb2312_1.txt");
+const GB2312_2: &str = include_str!("charset/gb2312_2.txt");
+
+//字符集
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset{
+    Gb2312Level1,
+    Gb2312Level2,
+    Other,
+}
+
+impl Charset{
+    pub const ALL: [Charset; 3] = [Charset::Gb2312Level1, Charset::Gb2312Level2, Charset::Other];
+
+    pub fn id(&self) -> &'static str{
+        match self{
+            Charset::Gb2312Level1 => "gb2312_1",
+            Charset::Gb2312Level2 => "gb2312_2",
+            Charset::Other => "other",
+        }
+    }
+
+    pub fn from_id(id:&str) -> Option<Charset>{
+        Charset::ALL.iter().find(|charset| charset.id() == id).cloned()
+    }
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            Charset::Gb2312Level1 => "GB2312一级",
+            Charset::Gb2312Level2 => "GB2312二级",
+            Charset::Other => "其他",
+        }
+    }
+}
+
+//排好序的字符, 用来二分查找
+fn sorted(text:&str) -> Vec<char>{
+    let mut chars: Vec<char> = text.trim().chars().collect();
+    chars.sort_unstable();
+    chars
+}
+
+pub fn charset(ch: char) -> Charset{
+    static LEVEL1: OnceLock<Vec<char>> = OnceLock::new();
+    static LEVEL2: OnceLock<Vec<char>> = OnceLock::new();
+    if LEVEL1.get_or_init(|| sorted(GB2312_1)).binary_search(&ch).is_ok(){
+        Charset::Gb2312Level1
+    }else if LEVEL2.get_or_init(|| sorted(GB2312_2)).binary_search(&ch).is_ok(){
+        Charset::Gb2312Level2
+    }else{
+        Charset::Other
+    }
+}
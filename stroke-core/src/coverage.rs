@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use crate::data::Strokes;
+use crate::script::{DataScript, Script, lint};
+use crate::review::{Reviews, Status};
+
+//文本中需要检查的字(去掉数字和标点), 拉丁字母的数据才检查ASCII字母, 其他数据中它们一般是夹杂的外文
+pub fn is_text_char(ch: char, script:&DataScript) -> bool{
+    ch.is_alphabetic() && (!ch.is_ascii() || script.main() == Script::Latin)
+}
+
+//文本中每个字出现的次数, 按次数从多到少排列
+pub fn frequencies(text:&str, script:&DataScript) -> Vec<(char, usize)>{
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for ch in text.chars().filter(|ch| is_text_char(*ch, script)){
+        *counts.entry(ch).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(char, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts
+}
+
+//文本的覆盖率报告, 每一项都是(字, 出现次数)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage{
+    //字的总次数
+    pub total: usize,
+    //不同的字
+    pub distinct: usize,
+    //数据中没有的字
+    pub missing: Vec<(char, usize)>,
+    //有但是没有确认的字
+    pub unreviewed: Vec<(char, usize)>,
+    //有但是有问题的字
+    pub lint: Vec<(char, usize)>,
+}
+
+impl Coverage{
+    pub fn new(text:&str, strokes:&HashMap<char, Strokes>, reviews:&Reviews, script:&DataScript) -> Coverage{
+        let counts = frequencies(text, script);
+        let mut coverage = Coverage{
+            total: counts.iter().map(|(_, count)| count).sum(),
+            distinct: counts.len(),
+            ..Coverage::default()
+        };
+        for (ch, count) in counts{
+            let strokes = match strokes.get(&ch){
+                Some(strokes) => strokes,
+                None => {
+                    coverage.missing.push((ch, count));
+                    continue;
+                }
+            };
+            if reviews.get(&ch).map(|review| review.status) != Some(Status::Verified){
+                coverage.unreviewed.push((ch, count));
+            }
+            if !lint(script.of(ch), strokes).is_empty(){
+                coverage.lint.push((ch, count));
+            }
+        }
+        coverage
+    }
+
+    //按出现次数计算的覆盖率(0~1)
+    pub fn ratio(&self) -> f64{
+        if self.total == 0{
+            return 1.0;
+        }
+        let missing: usize = self.missing.iter().map(|(_, count)| count).sum();
+        (self.total - missing) as f64 / self.total as f64
+    }
+
+    pub fn summary(&self) -> String{
+        format!("共{}个字({}个不同的字), 覆盖率{:.2}%, 缺少{}个字, 未确认{}个字, 有问题{}个字",
+            self.total, self.distinct, self.ratio() * 100.0, self.missing.len(), self.unreviewed.len(), self.lint.len())
+    }
+}
+
+//"字\t次数"每行一个
+pub fn format_list(list:&[(char, usize)]) -> String{
+    list.iter().map(|(ch, count)| format!("{}\t{}\n", ch, count)).collect()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::review::Review;
+
+    fn han() -> DataScript{
+        DataScript::detect(&['一'], None)
+    }
+
+    fn latin() -> DataScript{
+        DataScript::detect(&[], Some(Script::Latin))
+    }
+
+    #[test]
+    fn counts_text_chars(){
+        //数字、标点和空白不计算, 次数相同时按字符顺序
+        assert_eq!(frequencies("十一, 一二 3。一十\n", &han()), vec![('一', 3), ('十', 2), ('二', 1)]);
+        assert!(frequencies("123, !?", &han()).is_empty());
+    }
+
+    #[test]
+    fn filters_ascii_by_script(){
+        //汉字数据中夹杂的外文不检查, 拉丁字母的数据才检查ASCII字母
+        assert!(!is_text_char('a', &han()));
+        assert!(is_text_char('é', &han()));
+        assert!(is_text_char('a', &latin()));
+        assert!(!is_text_char('1', &latin()));
+        assert_eq!(frequencies("一a一b", &han()), vec![('一', 2)]);
+        assert_eq!(frequencies("一a一b", &latin()), vec![('一', 2), ('a', 1), ('b', 1)]);
+    }
+
+    #[test]
+    fn reports_missing_unreviewed_and_lint(){
+        let mut strokes: HashMap<char, Strokes> = HashMap::new();
+        strokes.insert('一', vec![vec![(100, 500), (900, 500)]]);
+        //只有一个点的笔画有问题
+        strokes.insert('二', vec![vec![(200, 300), (800, 300)], vec![(100, 700)]]);
+        let mut reviews = Reviews::new();
+        reviews.insert('一', Review{ status: Status::Verified, ..Review::default() });
+        reviews.insert('二', Review{ status: Status::NeedsWork, ..Review::default() });
+        let coverage = Coverage::new("一一一二十十千", &strokes, &reviews, &han());
+        assert_eq!((coverage.total, coverage.distinct), (7, 4));
+        assert_eq!(coverage.missing, vec![('十', 2), ('千', 1)]);
+        assert_eq!(coverage.unreviewed, vec![('二', 1)]);
+        assert_eq!(coverage.lint, vec![('二', 1)]);
+        assert!((coverage.ratio() - 4.0 / 7.0).abs() < 1e-9);
+        assert_eq!(format_list(&coverage.missing), "十\t2\n千\t1\n");
+    }
+
+    #[test]
+    fn empty_text_is_fully_covered(){
+        let coverage = Coverage::new("", &HashMap::new(), &Reviews::new(), &han());
+        assert_eq!(coverage, Coverage::default());
+        assert_eq!(coverage.ratio(), 1.0);
+    }
+}
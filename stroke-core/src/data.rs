@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use bincode::{deserialize, serialize, serialized_size};
+
+//一个字的所有笔画, 每一笔是若干个点(字形坐标0~1000)
+pub type Strokes = Vec<Vec<(u16, u16)>>;
+
+//STROKES.data的格式: 按顺序排列的所有字
+pub type StrokeList = Vec<(char, Strokes)>;
+
+//字形坐标的最大值
+pub const MAX_COORD: u16 = 1000;
+
+//读取STROKES.data(bincode序列化的Vec<(char, Vec<Vec<(u16, u16)>>)>)
+//gb2312.data(HashMap<char, Vec<Vec<(u16, u16)>>>)的编码和Vec相同, 也可以读取, 只是顺序不固定
+pub fn read_list(data:&[u8]) -> Result<StrokeList, String>{
+    deserialize(data).map_err(|err| format!("数据格式错误:{}", err))
+}
+
+//数据文件中字的顺序
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Order{
+    //按字符顺序排列(STROKES.data、OLD_STROKE.map)
+    Sorted,
+    //没有排序: HashMap格式(gb2312.data), 或者旧版本把新添加的字放在最后的Vec格式
+    Unsorted,
+}
+
+//读取Vec<(char, Vec<Vec<(u16, u16)>>)>或者HashMap<char, Vec<Vec<(u16, u16)>>>格式的数据
+//两种格式的bincode编码完全相同(个数后面是每个字的字符和笔画), 从数据中无法区分, 也不需要区分:
+//都按照(字符, 笔画)的列表读取, 返回的数据总是按字符顺序排列, 有重复的字时返回错误(不能静默丢掉其中一个)
+pub fn read_any(data:&[u8]) -> Result<(StrokeList, Order), String>{
+    let mut list = read_list(data)?;
+    if list.windows(2).all(|pair| pair[0].0 < pair[1].0){
+        return Ok((list, Order::Sorted));
+    }
+    list.sort_by_key(|(ch, _)| *ch);
+    let duplicates: Vec<char> = list.windows(2).filter(|pair| pair[0].0 == pair[1].0).map(|pair| pair[0].0).collect();
+    if !duplicates.is_empty(){
+        return Err(format!("数据中有{}个重复的字:{}", duplicates.len(), duplicates.iter().collect::<String>()));
+    }
+    Ok((list, Order::Unsorted))
+}
+
+pub fn write_list(list:&[(char, Strokes)]) -> Vec<u8>{
+    serialize(list).unwrap()
+}
+
+//序列化后的长度, 后面是附加的审核信息
+pub fn list_size(list:&[(char, Strokes)]) -> usize{
+    serialized_size(list).unwrap() as usize
+}
+
+//生成gb2312.data(bincode序列化的HashMap<char, Vec<Vec<(u16, u16)>>>)
+pub fn write_map(list:&[(char, Strokes)]) -> Vec<u8>{
+    let map: HashMap<char, &Strokes> = list.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    serialize(&map).unwrap()
+}
+
+//一个字的问题: 没有笔画、少于两个点的笔画、超出字形范围的点
+pub fn problems(strokes:&Strokes) -> Vec<String>{
+    let mut problems = vec![];
+    if strokes.is_empty(){
+        problems.push("没有笔画".to_string());
+    }
+    for (i, stroke) in strokes.iter().enumerate(){
+        if stroke.len() < 2{
+            problems.push(format!("第{}笔只有{}个点", i + 1, stroke.len()));
+        }
+        if let Some(point) = stroke.iter().find(|(x, y)| *x > MAX_COORD || *y > MAX_COORD){
+            problems.push(format!("第{}笔的点({},{})超出范围", i + 1, point.0, point.1));
+        }
+    }
+    problems
+}
+
+//检查数据中的问题: 重复的字以及每个字的问题
+pub fn validate(list:&[(char, Strokes)]) -> Vec<(char, String)>{
+    let mut problems = vec![];
+    let mut seen = HashSet::new();
+    for (ch, strokes) in list{
+        if !seen.insert(*ch){
+            problems.push((*ch, "重复的字".to_string()));
+        }
+        problems.extend(self::problems(strokes).into_iter().map(|problem| (*ch, problem)));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn list() -> StrokeList{
+        //按字符顺序排列
+        "一丁七万丈三上下".chars().enumerate()
+            .map(|(i, ch)| (ch, vec![vec![(i as u16 * 10, 0), (500, 500)]]))
+            .collect()
+    }
+
+    #[test]
+    fn reads_sorted_list(){
+        let (read, order) = read_any(&write_list(&list())).unwrap();
+        assert_eq!(order, Order::Sorted);
+        assert_eq!(read, list());
+    }
+
+    #[test]
+    fn sorts_unsorted_list_and_map(){
+        //旧版本添加的字在最后
+        let mut appended = list();
+        let last = appended.remove(0);
+        appended.push(last);
+        let (read, order) = read_any(&write_list(&appended)).unwrap();
+        assert_eq!(order, Order::Unsorted);
+        assert_eq!(read, list());
+        assert_eq!(read_any(&write_map(&list())).unwrap().0, list());
+    }
+
+    #[test]
+    fn rejects_duplicates(){
+        let mut list = list();
+        list.push(('丁', vec![]));
+        list.push(('三', vec![]));
+        assert_eq!(read_any(&write_list(&list)).unwrap_err(), "数据中有2个重复的字:丁三");
+    }
+
+    #[test]
+    fn reads_data_with_reviews_appended(){
+        let mut data = write_list(&list());
+        data.extend_from_slice(b"REVIEWS1 ...");
+        assert_eq!(read_any(&data).unwrap().0, list());
+        assert_eq!(list_size(&list()), write_list(&list()).len());
+    }
+
+    #[test]
+    fn rejects_garbage(){
+        assert!(read_any(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn validate_reports_problems(){
+        let mut list = list();
+        list.push(('一', vec![]));
+        list[1].1.push(vec![(1001, 0)]);
+        let problems: Vec<char> = validate(&list).into_iter().map(|(ch, _)| ch).collect();
+        assert_eq!(problems, vec!['丁', '丁', '一', '一']);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::data::Strokes;
+
+//一笔的变化
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrokeChange{
+    //新增的笔画(新数据中的下标)
+    Added(usize),
+    //删除的笔画(旧数据中的下标)
+    Removed(usize),
+    //点的位置移动超过了容差(笔画下标, 最大移动距离)
+    Moved(usize, f64),
+    //点数改变(笔画下标, 旧点数, 新点数)
+    Reshaped(usize, usize, usize),
+    //笔画顺序改变, 新数据中每一笔对应的旧笔画下标
+    Reordered(Vec<Option<usize>>),
+}
+
+//一个字的差异
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharDiff{
+    Added,
+    Removed,
+    Changed(Vec<StrokeChange>),
+}
+
+impl fmt::Display for StrokeChange{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            StrokeChange::Added(index) => write!(f, "新增第{}笔", index + 1),
+            StrokeChange::Removed(index) => write!(f, "删除第{}笔", index + 1),
+            StrokeChange::Moved(index, distance) => write!(f, "第{}笔移动{:.0}", index + 1, distance),
+            StrokeChange::Reshaped(index, old, new) => write!(f, "第{}笔{}点->{}点", index + 1, old, new),
+            StrokeChange::Reordered(order) => {
+                let order: Vec<String> = order.iter().map(|index| match index{
+                    Some(index) => format!("{}", index + 1),
+                    None => "-".to_string()
+                }).collect();
+                write!(f, "笔顺改为{}", order.join(","))
+            }
+        }
+    }
+}
+
+impl fmt::Display for CharDiff{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            CharDiff::Added => write!(f, "新增"),
+            CharDiff::Removed => write!(f, "删除"),
+            CharDiff::Changed(changes) => {
+                let changes: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+                write!(f, "{}", changes.join("; "))
+            }
+        }
+    }
+}
+
+//两笔对应点之间的最大距离, 点数不同时返回None
+fn max_distance(a:&[(u16, u16)], b:&[(u16, u16)]) -> Option<f64>{
+    if a.len() != b.len(){
+        return None;
+    }
+    Some(a.iter().zip(b.iter()).map(|(p, q)| {
+        let (dx, dy) = (p.0 as f64 - q.0 as f64, p.1 as f64 - q.1 as f64);
+        (dx * dx + dy * dy).sqrt()
+    }).fold(0.0, f64::max))
+}
+
+//比较一个字的两组笔画, 点的移动不超过tolerance时认为没有变化
+pub fn diff_strokes(old:&Strokes, new:&Strokes, tolerance: f64) -> Vec<StrokeChange>{
+    let same = |a: &[(u16, u16)], b: &[(u16, u16)]| max_distance(a, b).map(|d| d <= tolerance).unwrap_or(false);
+    //新笔画对应的旧笔画, 优先对应同一位置的笔画
+    let mut order: Vec<Option<usize>> = vec![None; new.len()];
+    let mut used = vec![false; old.len()];
+    for (j, stroke) in new.iter().enumerate(){
+        if j < old.len() && same(&old[j], stroke){
+            order[j] = Some(j);
+            used[j] = true;
+        }
+    }
+    for (j, stroke) in new.iter().enumerate(){
+        if order[j].is_some(){
+            continue;
+        }
+        if let Some(i) = (0..old.len()).find(|&i| !used[i] && same(&old[i], stroke)){
+            order[j] = Some(i);
+            used[i] = true;
+        }
+    }
+
+    let mut changes = vec![];
+    //没有对应的笔画: 同一位置的旧笔画没有被使用时认为是修改了这一笔
+    for (j, stroke) in new.iter().enumerate(){
+        if order[j].is_some(){
+            continue;
+        }
+        if j < old.len() && !used[j]{
+            used[j] = true;
+            order[j] = Some(j);
+            match max_distance(&old[j], stroke){
+                Some(distance) => changes.push(StrokeChange::Moved(j, distance)),
+                None => changes.push(StrokeChange::Reshaped(j, old[j].len(), stroke.len())),
+            }
+        }else{
+            changes.push(StrokeChange::Added(j));
+        }
+    }
+    for (i, used) in used.iter().enumerate(){
+        if !used{
+            changes.push(StrokeChange::Removed(i));
+        }
+    }
+    if order.iter().enumerate().any(|(j, i)| matches!(i, Some(i) if *i != j)){
+        changes.insert(0, StrokeChange::Reordered(order));
+    }
+    changes
+}
+
+//比较两个数据集, 按照order的顺序返回有差异的字, 最后是只在旧数据中存在的字
+pub fn diff(old:&HashMap<char, Strokes>, new:&HashMap<char, Strokes>, order:&[char], tolerance: f64) -> Vec<(char, CharDiff)>{
+    let mut diffs = vec![];
+    for ch in order{
+        match (old.get(ch), new.get(ch)){
+            (None, Some(_)) => diffs.push((*ch, CharDiff::Added)),
+            (Some(a), Some(b)) => {
+                let changes = diff_strokes(a, b, tolerance);
+                if !changes.is_empty(){
+                    diffs.push((*ch, CharDiff::Changed(changes)));
+                }
+            }
+            _ => ()
+        }
+    }
+    let mut removed: Vec<char> = old.keys().filter(|ch| !new.contains_key(ch)).cloned().collect();
+    removed.sort();
+    diffs.extend(removed.into_iter().map(|ch| (ch, CharDiff::Removed)));
+    diffs
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn strokes() -> Strokes{
+        vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]]
+    }
+
+    #[test]
+    fn small_moves_are_within_tolerance(){
+        let mut new = strokes();
+        new[0][1] = (903, 504);
+        assert!(diff_strokes(&strokes(), &new, 5.0).is_empty());
+        assert_eq!(diff_strokes(&strokes(), &new, 4.0), vec![StrokeChange::Moved(0, 5.0)]);
+    }
+
+    #[test]
+    fn reshaped_added_and_removed(){
+        let mut new = strokes();
+        new[1].push((450, 850));
+        assert_eq!(diff_strokes(&strokes(), &new, 5.0), vec![StrokeChange::Reshaped(1, 2, 3)]);
+        new.push(vec![(300, 300), (700, 300)]);
+        assert_eq!(diff_strokes(&strokes(), &new, 5.0)[1], StrokeChange::Added(2));
+        assert_eq!(diff_strokes(&strokes(), &strokes()[..1].to_vec(), 5.0), vec![StrokeChange::Removed(1)]);
+    }
+
+    #[test]
+    fn reordered_strokes(){
+        let old = strokes();
+        let new = vec![old[1].clone(), old[0].clone()];
+        let changes = diff_strokes(&old, &new, 5.0);
+        assert_eq!(changes, vec![StrokeChange::Reordered(vec![Some(1), Some(0)])]);
+        assert_eq!(changes[0].to_string(), "笔顺改为2,1");
+    }
+
+    #[test]
+    fn diff_datasets(){
+        let old: HashMap<char, Strokes> = vec![('十', strokes()), ('一', vec![vec![(100, 500), (900, 500)]])].into_iter().collect();
+        let mut new = old.clone();
+        new.remove(&'一');
+        new.get_mut(&'十').unwrap()[0][0] = (100, 520);
+        new.insert('丨', vec![vec![(500, 100), (500, 900)]]);
+        let result = diff(&old, &new, &['十', '丨'], 5.0);
+        assert_eq!(result, vec![
+            ('十', CharDiff::Changed(vec![StrokeChange::Moved(0, 20.0)])),
+            ('丨', CharDiff::Added),
+            ('一', CharDiff::Removed),
+        ]);
+        assert!(diff(&old, &new, &['十', '丨'], 20.0).iter().all(|(ch, _)| *ch != '十'));
+    }
+}
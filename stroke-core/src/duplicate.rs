@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use crate::data::Strokes;
+use crate::matching::{resample, to_points};
+use crate::recognize::{Shape, shape, stroke_cost};
+use crate::view::GLYPH_SIZE;
+
+//归一化之后平均每一笔的距离小于这个值认为几乎相同
+pub const NEAR_THRESHOLD: f64 = 0.02;
+
+//两个笔画几乎相同的字
+#[derive(Clone, Debug, PartialEq)]
+pub struct Duplicate{
+    pub a: char,
+    pub b: char,
+    //归一化之后按笔顺对应的平均每一笔的距离, 0表示形状相同
+    pub score: f64,
+    //笔画数据完全相同(没有归一化)
+    pub identical: bool,
+}
+
+//按笔顺对应比较两个字, 距离超过limit时提前返回None
+fn in_order_score(a:&Shape, b:&Shape, limit: f64) -> Option<f64>{
+    let budget = limit * a.len() as f64;
+    let mut total = 0.0;
+    for (x, y) in a.iter().zip(b.iter()){
+        total += stroke_cost(x, y);
+        if total > budget{
+            return None;
+        }
+    }
+    Some(total / a.len() as f64)
+}
+
+//笔画数相同, 并且归一化之后(忽略位置和大小)按笔顺比较距离小于threshold的所有字对
+pub fn duplicates(list:&[(char, Strokes)], threshold: f64) -> Vec<Duplicate>{
+    let mut groups: HashMap<usize, Vec<(char, &Strokes, Shape)>> = HashMap::new();
+    for (ch, strokes) in list{
+        if !strokes.is_empty(){
+            groups.entry(strokes.len()).or_default().push((*ch, strokes, shape(strokes)));
+        }
+    }
+    let mut result = vec![];
+    for group in groups.values(){
+        for (i, (a, strokes_a, shape_a)) in group.iter().enumerate(){
+            for (b, strokes_b, shape_b) in &group[i + 1..]{
+                //完全相同的字一定列出来(空的笔画按少写了一笔计算距离)
+                let identical = strokes_a == strokes_b;
+                let score = if identical { Some(0.0) } else { in_order_score(shape_a, shape_b, threshold) };
+                if let Some(score) = score{
+                    result.push(Duplicate{ a: *a, b: *b, score, identical });
+                }
+            }
+        }
+    }
+    result.sort_by(|x, y| x.score.total_cmp(&y.score).then(x.a.cmp(&y.a)).then(x.b.cmp(&y.b)));
+    result
+}
+
+//参考字体中一个字的点阵, 按位保存每个格子是否有笔迹, 以及笔迹附近(笔画宽度以内)的格子
+pub struct Bitmap{
+    pub size: usize,
+    ink: Vec<u64>,
+    near: Vec<u64>,
+}
+
+//比较笔画和字形时每一笔采样的点数
+const INK_SAMPLES: usize = 16;
+
+//每个格子对应的位
+fn set_bit(bits:&mut [u64], index: usize){
+    bits[index / 64] |= 1 << (index % 64);
+}
+
+fn get_bit(bits:&[u64], index: usize) -> bool{
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn count_bits(bits:&[u64]) -> usize{
+    bits.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+//笔画宽度大约是字的1/12
+fn ink_radius(size: usize) -> usize{
+    (size / 12).max(1)
+}
+
+//(cx, cy)周围radius格以内的所有格子
+fn around(size: usize, (cx, cy): (usize, usize), radius: usize) -> impl Iterator<Item = usize>{
+    let (x0, x1) = (cx.saturating_sub(radius), (cx + radius).min(size - 1));
+    (cy.saturating_sub(radius)..=(cy + radius).min(size - 1)).flat_map(move |y| (x0..=x1).map(move |x| y * size + x))
+}
+
+impl Bitmap{
+    //ink按行排列, 每个格子是否有笔迹
+    pub fn new(size: usize, ink:&[bool]) -> Bitmap{
+        let words = (size * size).div_ceil(64);
+        let (mut bits, mut near) = (vec![0; words], vec![0; words]);
+        let radius = ink_radius(size);
+        for index in (0..size * size).filter(|index| ink.get(*index) == Some(&true)){
+            set_bit(&mut bits, index);
+            for cell in around(size, (index % size, index / size), radius){
+                set_bit(&mut near, cell);
+            }
+        }
+        Bitmap{ size, ink: bits, near }
+    }
+
+    //RGBA数据, 不透明度超过一半的像素算有笔迹
+    pub fn from_rgba(size: usize, rgba:&[u8]) -> Bitmap{
+        let ink: Vec<bool> = rgba.chunks(4).map(|pixel| pixel.get(3).cloned().unwrap_or(0) > 127).collect();
+        Bitmap::new(size, &ink)
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.ink.iter().all(|word| *word == 0)
+    }
+}
+
+//一个字的笔画在点阵上经过的格子, 和多个字形比较时只计算一次
+pub struct StrokeInk{
+    size: usize,
+    //笔画上采样的点所在的格子
+    cells: Vec<usize>,
+    //笔画经过的格子(加上笔画宽度)
+    covered: Vec<u64>,
+}
+
+impl StrokeInk{
+    //size是点阵的大小
+    pub fn new(strokes:&Strokes, size: usize) -> StrokeInk{
+        let mut covered = vec![0; (size * size).div_ceil(64)];
+        if size == 0{
+            return StrokeInk{ size, cells: vec![], covered };
+        }
+        let scale = size as f64 / GLYPH_SIZE;
+        let cells: Vec<(usize, usize)> = strokes.iter()
+            .flat_map(|stroke| resample(&to_points(stroke), INK_SAMPLES))
+            .map(|(x, y)| (((x * scale) as usize).min(size - 1), ((y * scale) as usize).min(size - 1)))
+            .collect();
+        let radius = ink_radius(size);
+        for cell in &cells{
+            for index in around(size, *cell, radius){
+                set_bit(&mut covered, index);
+            }
+        }
+        StrokeInk{ size, cells: cells.into_iter().map(|(x, y)| y * size + x).collect(), covered }
+    }
+
+    //和参考字形的吻合程度(0~1): 笔画上的点落在笔迹附近的比例和笔迹被笔画经过的比例的调和平均
+    pub fn matches(&self, bitmap:&Bitmap) -> f64{
+        if self.cells.is_empty() || bitmap.size != self.size || bitmap.is_empty(){
+            return 0.0;
+        }
+        let precision = self.cells.iter().filter(|cell| get_bit(&bitmap.near, **cell)).count() as f64 / self.cells.len() as f64;
+        let covered: usize = bitmap.ink.iter().zip(self.covered.iter()).map(|(ink, covered)| (ink & covered).count_ones() as usize).sum();
+        let recall = covered as f64 / count_bits(&bitmap.ink) as f64;
+        if precision + recall == 0.0{
+            0.0
+        }else{
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+//笔画和参考字形的吻合程度, 和多个字形比较时用StrokeInk
+pub fn ink_match(strokes:&Strokes, bitmap:&Bitmap) -> f64{
+    StrokeInk::new(strokes, bitmap.size).matches(bitmap)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn finds_identical_and_near_duplicates(){
+        let list = vec![
+            ('一', vec![vec![(100, 500), (900, 500)]]),
+            //位置和大小不同, 形状相同
+            ('乛', vec![vec![(200, 300), (600, 300)]]),
+            ('丨', vec![vec![(500, 100), (500, 900)]]),
+            ('十', vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]]),
+            ('㐅', vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]]),
+            ('空', vec![]),
+        ];
+        let found = duplicates(&list, NEAR_THRESHOLD);
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().any(|d| (d.a, d.b) == ('十', '㐅') && d.identical && d.score == 0.0));
+        assert!(found.iter().any(|d| (d.a, d.b) == ('一', '乛') && !d.identical && d.score < NEAR_THRESHOLD));
+    }
+
+    #[test]
+    fn empty_strokes_do_not_panic(){
+        let list = vec![('一', vec![vec![], vec![(100, 500), (900, 500)]]), ('二', vec![vec![], vec![(100, 500), (900, 500)]])];
+        let found = duplicates(&list, NEAR_THRESHOLD);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].identical);
+    }
+
+    #[test]
+    fn ink_match_prefers_matching_glyph(){
+        //12x12的点阵, 中间一条横线
+        let size = 12;
+        let ink: Vec<bool> = (0..size * size).map(|i| i / size == 6 && i % size >= 1 && i % size <= 10).collect();
+        let bitmap = Bitmap::new(size, &ink);
+        let horizontal = ink_match(&vec![vec![(100, 540), (900, 540)]], &bitmap);
+        let vertical = ink_match(&vec![vec![(500, 100), (500, 900)]], &bitmap);
+        assert!(horizontal > 0.9);
+        assert!(vertical < horizontal);
+        assert_eq!(ink_match(&vec![], &bitmap), 0.0);
+        assert_eq!(ink_match(&vec![vec![(100, 540)]], &Bitmap::new(size, &vec![false; size * size])), 0.0);
+    }
+
+    #[test]
+    fn stroke_ink_matches_like_direct_comparison(){
+        //和原来逐个格子检查的结果相同
+        let size = 48;
+        let ink: Vec<bool> = (0..size * size).map(|i| (i / size == 24 && i % size >= 4 && i % size <= 44) || (i % size == 24 && i / size >= 4)).collect();
+        let bitmap = Bitmap::new(size, &ink);
+        let radius = ink_radius(size);
+        let strokes = vec![vec![(100, 510), (500, 480), (900, 510)], vec![(500, 100), (520, 900)], vec![(200, 200), (300, 300)]];
+        let cells: Vec<(usize, usize)> = strokes.iter()
+            .flat_map(|stroke| resample(&to_points(stroke), INK_SAMPLES))
+            .map(|(x, y)| (((x * 0.048) as usize).min(size - 1), ((y * 0.048) as usize).min(size - 1)))
+            .collect();
+        let near = cells.iter().filter(|cell| around(size, **cell, radius).any(|index| ink[index])).count();
+        let covered: Vec<usize> = cells.iter().flat_map(|cell| around(size, *cell, radius)).collect();
+        let total = ink.iter().filter(|ink| **ink).count();
+        let hit = (0..size * size).filter(|index| ink[*index] && covered.contains(index)).count();
+        let (precision, recall) = (near as f64 / cells.len() as f64, hit as f64 / total as f64);
+        let expected = 2.0 * precision * recall / (precision + recall);
+        let prepared = StrokeInk::new(&strokes, size);
+        assert!((prepared.matches(&bitmap) - expected).abs() < 1e-9);
+        assert!((ink_match(&strokes, &bitmap) - expected).abs() < 1e-9);
+        //点阵大小不同时不比较
+        assert_eq!(prepared.matches(&Bitmap::new(12, &[true; 144])), 0.0);
+    }
+}
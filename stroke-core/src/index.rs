@@ -0,0 +1,110 @@
+use std::iter::FromIterator;
+use std::ops::Deref;
+
+//按照字符顺序排列、没有重复的字符索引
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CharIndex{
+    chars: Vec<char>,
+}
+
+impl CharIndex{
+    pub fn new() -> CharIndex{
+        CharIndex::default()
+    }
+
+    pub fn contains(&self, ch: char) -> bool{
+        self.chars.binary_search(&ch).is_ok()
+    }
+
+    //插入到字符顺序中对应的位置, 返回插入的位置(已经存在时返回None)
+    pub fn insert(&mut self, ch: char) -> Option<usize>{
+        match self.chars.binary_search(&ch){
+            Ok(_) => None,
+            Err(index) => {
+                self.chars.insert(index, ch);
+                Some(index)
+            }
+        }
+    }
+
+    //删除字符, 返回原来的位置(不存在时返回None)
+    pub fn remove(&mut self, ch: char) -> Option<usize>{
+        let index = self.chars.binary_search(&ch).ok()?;
+        self.chars.remove(index);
+        Some(index)
+    }
+
+    //把from改为to, 移动到to在字符顺序中的位置
+    pub fn rename(&mut self, from: char, to: char) -> Result<usize, String>{
+        if from == to{
+            return self.chars.binary_search(&from).map_err(|_| format!("字符不存在:{}", from));
+        }
+        if self.contains(to){
+            return Err(format!("字符已存在:{}", to));
+        }
+        if self.remove(from).is_none(){
+            return Err(format!("字符不存在:{}", from));
+        }
+        Ok(self.insert(to).unwrap())
+    }
+}
+
+impl Deref for CharIndex{
+    type Target = [char];
+
+    fn deref(&self) -> &[char]{
+        &self.chars
+    }
+}
+
+//从任意顺序的字符生成, 排序并去掉重复的字
+impl FromIterator<char> for CharIndex{
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> CharIndex{
+        let mut chars: Vec<char> = iter.into_iter().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        CharIndex{ chars }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn insert_keeps_order(){
+        let mut index = CharIndex::new();
+        assert_eq!(index.insert('十'), Some(0));
+        assert_eq!(index.insert('一'), Some(0));
+        assert_eq!(index.insert('二'), Some(1));
+        assert_eq!(index.insert('一'), None);
+        assert_eq!(&index[..], &['一', '二', '十']);
+        assert!(index.contains('二') && !index.contains('三'));
+    }
+
+    #[test]
+    fn remove_returns_position(){
+        let mut index: CharIndex = "十一二".chars().collect();
+        assert_eq!(index.remove('二'), Some(1));
+        assert_eq!(index.remove('二'), None);
+        assert_eq!(&index[..], &['一', '十']);
+    }
+
+    #[test]
+    fn rename_moves_char(){
+        let mut index: CharIndex = "一二十".chars().collect();
+        assert_eq!(index.rename('一', '千'), Ok(2));
+        assert_eq!(&index[..], &['二', '十', '千']);
+        assert_eq!(index.rename('二', '二'), Ok(0));
+        assert!(index.rename('二', '十').is_err());
+        assert!(index.rename('一', '丁').is_err());
+        assert_eq!(&index[..], &['二', '十', '千']);
+    }
+
+    #[test]
+    fn from_iter_sorts_and_dedups(){
+        let index: CharIndex = "二一二十一".chars().collect();
+        assert_eq!(&index[..], &['一', '二', '十']);
+        assert_eq!(index, "十二一".chars().collect());
+    }
+}
@@ -0,0 +1,18 @@
+//笔画数据的格式、比较、合并和检查, 不依赖浏览器, 页面(rust-stroke-editor)和命令行工具(stroke-tools)共用
+pub mod data;
+pub mod patch;
+pub mod diff;
+pub mod merge;
+pub mod svg;
+pub mod review;
+pub mod charset;
+pub mod reading;
+pub mod coverage;
+pub mod index;
+pub mod variant;
+pub mod script;
+pub mod matching;
+pub mod recognize;
+pub mod duplicate;
+pub mod simplify;
+pub mod view;
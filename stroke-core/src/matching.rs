@@ -0,0 +1,234 @@
+//把用户写的一笔和数据中的笔画比较(字形坐标)
+
+//比较时每一笔重新采样的点数
+pub const SAMPLES: usize = 32;
+//平均距离小于这个值(字形坐标)认为形状相同
+const MAX_DISTANCE: f64 = 120.0;
+//起点、终点的距离小于这个值认为位置正确
+const MAX_END_ERROR: f64 = 200.0;
+//方向的余弦大于这个值认为方向正确
+const MIN_DIRECTION: f64 = 0.5;
+//首尾距离小于这个值的笔画(点)不检查方向
+const MIN_CHORD: f64 = 80.0;
+
+//比较的结果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokeMatch{
+    //按长度均匀采样后对应点的平均距离
+    pub distance: f64,
+    //归一化(移到原点并缩放到单位大小)之后的平均DTW距离, 只比较形状
+    pub dtw: f64,
+    //归一化之后的离散Fréchet距离
+    pub frechet: f64,
+    //起点到终点方向的余弦(-1~1, 1表示方向相同), 点没有方向时为1
+    pub direction: f64,
+    pub start_error: f64,
+    pub end_error: f64,
+}
+
+impl StrokeMatch{
+    pub fn accepted(&self) -> bool{
+        self.distance < MAX_DISTANCE && self.direction > MIN_DIRECTION
+            && self.start_error < MAX_END_ERROR && self.end_error < MAX_END_ERROR
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64{
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+//按长度均匀采样n个点(包含首尾两点)
+pub fn resample(points:&[(f64, f64)], n: usize) -> Vec<(f64, f64)>{
+    if points.is_empty() || n == 0{
+        return vec![];
+    }
+    let length: f64 = points.windows(2).map(|w| distance(w[0], w[1])).sum();
+    if points.len() == 1 || length == 0.0 || n == 1{
+        return vec![points[0]; n];
+    }
+    let step = length / (n - 1) as f64;
+    let mut result = vec![points[0]];
+    //当前线段的起点和已经走过的长度
+    let (mut index, mut walked) = (0, 0.0);
+    for i in 1..n - 1{
+        let target = step * i as f64;
+        while index + 1 < points.len() - 1 && walked + distance(points[index], points[index + 1]) < target{
+            walked += distance(points[index], points[index + 1]);
+            index += 1;
+        }
+        let (a, b) = (points[index], points[index + 1]);
+        let segment = distance(a, b);
+        let t = if segment > 0.0 { ((target - walked) / segment).clamp(0.0, 1.0) } else { 0.0 };
+        result.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+    }
+    result.push(points[points.len() - 1]);
+    result
+}
+
+pub fn to_points(stroke:&[(u16, u16)]) -> Vec<(f64, f64)>{
+    stroke.iter().map(|(x, y)| (*x as f64, *y as f64)).collect()
+}
+
+//移到以包围盒中心为原点, 按包围盒较长的一边缩放到单位大小(点不缩放)
+pub fn normalize(points:&[(f64, f64)]) -> Vec<(f64, f64)>{
+    if points.is_empty(){
+        return vec![];
+    }
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for (x, y) in points{
+        min_x = min_x.min(*x);
+        min_y = min_y.min(*y);
+        max_x = max_x.max(*x);
+        max_y = max_y.max(*y);
+    }
+    let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    let size = (max_x - min_x).max(max_y - min_y);
+    let scale = if size < MIN_CHORD { 1.0 / MIN_CHORD } else { 1.0 / size };
+    points.iter().map(|(x, y)| ((x - cx) * scale, (y - cy) * scale)).collect()
+}
+
+//动态时间规整: 允许两条折线的速度不同, 返回对齐路径上的平均距离
+pub fn dtw(a:&[(f64, f64)], b:&[(f64, f64)]) -> f64{
+    if a.is_empty() || b.is_empty(){
+        return f64::INFINITY;
+    }
+    //cost[j]: 到(i, j)的最小总距离和路径长度
+    let mut cost = vec![(f64::INFINITY, 0usize); b.len() + 1];
+    cost[0] = (0.0, 0);
+    for p in a{
+        let mut diagonal = cost[0];
+        cost[0] = (f64::INFINITY, 0);
+        for (j, q) in b.iter().enumerate(){
+            let up = cost[j + 1];
+            let best = [diagonal, up, cost[j]].iter().cloned()
+                .min_by(|x, y| x.0.total_cmp(&y.0)).unwrap();
+            diagonal = up;
+            cost[j + 1] = (best.0 + distance(*p, *q), best.1 + 1);
+        }
+    }
+    let (total, steps) = cost[b.len()];
+    total / steps as f64
+}
+
+//离散Fréchet距离: 两条折线按顺序同时前进时最大距离的最小值
+pub fn frechet(a:&[(f64, f64)], b:&[(f64, f64)]) -> f64{
+    if a.is_empty() || b.is_empty(){
+        return f64::INFINITY;
+    }
+    //上一行和这一行的结果
+    let mut previous = vec![f64::INFINITY; b.len()];
+    let mut current = vec![0.0; b.len()];
+    for (i, p) in a.iter().enumerate(){
+        for (j, q) in b.iter().enumerate(){
+            let reach = match (i, j){
+                (0, 0) => 0.0,
+                (0, _) => current[j - 1],
+                (_, 0) => previous[0],
+                _ => previous[j].min(previous[j - 1]).min(current[j - 1]),
+            };
+            current[j] = reach.max(distance(*p, *q));
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len() - 1]
+}
+
+//比较两笔, 都是数据中的坐标
+pub fn compare(a:&[(u16, u16)], b:&[(u16, u16)]) -> StrokeMatch{
+    match_stroke(&to_points(a), b)
+}
+
+//比较写的一笔和数据中的一笔
+pub fn match_stroke(drawn:&[(f64, f64)], expected:&[(u16, u16)]) -> StrokeMatch{
+    let expected = to_points(expected);
+    let (a, b) = (resample(drawn, SAMPLES), resample(&expected, SAMPLES));
+    if a.is_empty() || b.is_empty(){
+        return StrokeMatch{
+            distance: f64::INFINITY,
+            dtw: f64::INFINITY,
+            frechet: f64::INFINITY,
+            direction: -1.0,
+            start_error: f64::INFINITY,
+            end_error: f64::INFINITY,
+        };
+    }
+    let mean = a.iter().zip(b.iter()).map(|(p, q)| distance(*p, *q)).sum::<f64>() / SAMPLES as f64;
+    let (da, db) = ((a[SAMPLES - 1].0 - a[0].0, a[SAMPLES - 1].1 - a[0].1), (b[SAMPLES - 1].0 - b[0].0, b[SAMPLES - 1].1 - b[0].1));
+    let (la, lb) = (da.0.hypot(da.1), db.0.hypot(db.1));
+    let direction = if lb < MIN_CHORD || la == 0.0{
+        1.0
+    }else{
+        (da.0 * db.0 + da.1 * db.1) / (la * lb)
+    };
+    let (na, nb) = (normalize(&a), normalize(&b));
+    StrokeMatch{
+        distance: mean,
+        dtw: dtw(&na, &nb),
+        frechet: frechet(&na, &nb),
+        direction,
+        start_error: distance(a[0], b[0]),
+        end_error: distance(a[SAMPLES - 1], b[SAMPLES - 1]),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool{
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn resample_walks_along_length(){
+        assert_eq!(resample(&[(0.0, 0.0), (10.0, 0.0)], 3), vec![(0.0, 0.0), (5.0, 0.0), (10.0, 0.0)]);
+        assert_eq!(resample(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], 5),
+            vec![(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (10.0, 5.0), (10.0, 10.0)]);
+        //点和空笔画
+        assert_eq!(resample(&[(3.0, 4.0)], 3), vec![(3.0, 4.0); 3]);
+        assert!(resample(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn normalize_centers_and_scales(){
+        assert_eq!(normalize(&[(100.0, 100.0), (300.0, 200.0)]), vec![(-0.5, -0.25), (0.5, 0.25)]);
+    }
+
+    #[test]
+    fn dtw_on_known_inputs(){
+        let a = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)];
+        let b = [(0.0, 1.0), (1.0, 1.0), (2.0, 1.0)];
+        assert!(close(dtw(&a, &a), 0.0));
+        assert!(close(dtw(&a, &b), 1.0));
+        //速度不同: 路径(0,0)(1,1)(1,2), 总距离1, 3步
+        assert!(close(dtw(&[(0.0, 0.0), (2.0, 0.0)], &a), 1.0 / 3.0));
+        assert_eq!(dtw(&[], &a), f64::INFINITY);
+    }
+
+    #[test]
+    fn frechet_on_known_inputs(){
+        let a = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)];
+        let b = [(0.0, 1.0), (1.0, 1.0), (2.0, 1.0)];
+        assert!(close(frechet(&a, &a), 0.0));
+        assert!(close(frechet(&a, &b), 1.0));
+        assert!(close(frechet(&[(0.0, 0.0), (2.0, 0.0)], &a), 1.0));
+        //方向相反时至少是首尾的距离
+        let reversed: Vec<(f64, f64)> = a.iter().rev().cloned().collect();
+        assert!(close(frechet(&a, &reversed), 2.0));
+        assert_eq!(frechet(&a, &[]), f64::INFINITY);
+    }
+
+    #[test]
+    fn match_stroke_checks_direction(){
+        let stroke = [(100, 500), (900, 500)];
+        let same = compare(&stroke, &stroke);
+        assert!(same.accepted());
+        assert!(close(same.distance, 0.0) && close(same.direction, 1.0));
+        let reversed = compare(&[(900, 500), (100, 500)], &stroke);
+        assert!(close(reversed.direction, -1.0));
+        assert!(!reversed.accepted());
+        //稍有偏移仍然接受
+        assert!(match_stroke(&[(120.0, 520.0), (500.0, 530.0), (880.0, 510.0)], &stroke).accepted());
+        assert!(!match_stroke(&[], &stroke).accepted());
+    }
+}
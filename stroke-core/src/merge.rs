@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+use crate::data::{StrokeList, Strokes};
+
+//合并冲突: 双方都修改了同一个字并且结果不同(None表示不存在或者被删除)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict{
+    pub ch: char,
+    pub base: Option<Strokes>,
+    pub ours: Option<Strokes>,
+    pub theirs: Option<Strokes>,
+}
+
+//合并结果, 冲突的字保留ours的版本
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Merged{
+    pub list: StrokeList,
+    pub conflicts: Vec<Conflict>,
+}
+
+//三方合并: base是共同的原始数据, ours和theirs是各自修改后的数据
+//结果按照ours的顺序, 只在theirs中新增的字插入到字符顺序中对应的位置
+pub fn merge(base:&[(char, Strokes)], ours:&[(char, Strokes)], theirs:&[(char, Strokes)]) -> Merged{
+    let base_map: HashMap<char, &Strokes> = base.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let ours_map: HashMap<char, &Strokes> = ours.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let theirs_map: HashMap<char, &Strokes> = theirs.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+
+    let mut merged = Merged::default();
+    //合并一个字, 返回结果(None表示删除)
+    let mut pick = |ch: char| -> Option<Strokes>{
+        let (b, o, t) = (base_map.get(&ch).cloned(), ours_map.get(&ch).cloned(), theirs_map.get(&ch).cloned());
+        if o == t || t == b{
+            o.cloned()
+        }else if o == b{
+            t.cloned()
+        }else{
+            merged.conflicts.push(Conflict{
+                ch,
+                base: b.cloned(),
+                ours: o.cloned(),
+                theirs: t.cloned(),
+            });
+            o.cloned()
+        }
+    };
+
+    let mut list = vec![];
+    let mut seen = HashSet::new();
+    for (ch, _) in ours.iter().chain(base.iter()).chain(theirs.iter()){
+        if !seen.insert(*ch){
+            continue;
+        }
+        if let Some(strokes) = pick(*ch){
+            if ours_map.contains_key(ch){
+                list.push((*ch, strokes));
+            }else{
+                let index = list.partition_point(|(c, _): &(char, Strokes)| c < ch);
+                list.insert(index, (*ch, strokes));
+            }
+        }
+    }
+    merged.list = list;
+    merged
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn stroke(y: u16) -> Strokes{
+        vec![vec![(100, y), (900, y)]]
+    }
+
+    #[test]
+    fn non_overlapping_changes_are_merged(){
+        let base = vec![('一', stroke(500)), ('二', stroke(300)), ('三', stroke(200))];
+        let ours = vec![('一', stroke(510)), ('二', stroke(300)), ('三', stroke(200))];
+        //他们修改了"二", 删除了"三", 新增了"丁"
+        let theirs = vec![('一', stroke(500)), ('丁', stroke(200)), ('二', stroke(320))];
+        let merged = merge(&base, &ours, &theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.list, vec![('一', stroke(510)), ('丁', stroke(200)), ('二', stroke(320))]);
+    }
+
+    #[test]
+    fn same_change_is_not_a_conflict(){
+        let base = vec![('一', stroke(500))];
+        let changed = vec![('一', stroke(480))];
+        let merged = merge(&base, &changed, &changed);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.list, changed);
+    }
+
+    #[test]
+    fn conflicts_keep_ours(){
+        let base = vec![('一', stroke(500)), ('二', stroke(300))];
+        let ours = vec![('一', stroke(510)), ('二', stroke(310))];
+        //他们修改了"一", 删除了"二"
+        let theirs = vec![('一', stroke(490))];
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(merged.list, ours);
+        assert_eq!(merged.conflicts, vec![
+            Conflict{ ch: '一', base: Some(stroke(500)), ours: Some(stroke(510)), theirs: Some(stroke(490)) },
+            Conflict{ ch: '二', base: Some(stroke(300)), ours: Some(stroke(310)), theirs: None },
+        ]);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use bincode::{deserialize, serialize, serialized_size};
+use crate::data::{StrokeList, Strokes};
+use crate::review::{ReviewChanges, append_changes, changes_size, read_changes};
+use crate::variant::{Variants, append_variants, read_variants};
+
+//一个字的修改, 修改和删除带有原来的笔画, 应用时用来检查冲突
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Change{
+    //新增的字
+    Added(Strokes),
+    //修改了笔画的字(原来的笔画, 新的笔画)
+    Modified(Strokes, Strokes),
+    //删除的字(原来的笔画)
+    Removed(Strokes),
+}
+
+//补丁: 只包含新增、修改、删除的字
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Patch{
+    pub changes: Vec<(char, Change)>,
+}
+
+impl Patch{
+    //比较原始数据和当前数据生成补丁, 新增和修改的字按照order的顺序, 删除的字按照字符顺序
+    pub fn diff(base:&HashMap<char, Strokes>, current:&HashMap<char, Strokes>, order:&[char]) -> Patch{
+        let mut changes = vec![];
+        for ch in order{
+            match (base.get(ch), current.get(ch)){
+                (None, Some(strokes)) => changes.push((*ch, Change::Added(strokes.clone()))),
+                (Some(old), Some(strokes)) if old != strokes => changes.push((*ch, Change::Modified(old.clone(), strokes.clone()))),
+                _ => ()
+            }
+        }
+        let mut removed: Vec<(&char, &Strokes)> = base.iter().filter(|(ch, _)| !current.contains_key(ch)).collect();
+        removed.sort_by_key(|(ch, _)| **ch);
+        changes.extend(removed.into_iter().map(|(ch, strokes)| (*ch, Change::Removed(strokes.clone()))));
+        Patch{ changes }
+    }
+
+    //(新增, 修改, 删除)的字数
+    pub fn counts(&self) -> (usize, usize, usize){
+        let mut counts = (0, 0, 0);
+        for (_, change) in &self.changes{
+            match change{
+                Change::Added(_) => counts.0 += 1,
+                Change::Modified(..) => counts.1 += 1,
+                Change::Removed(_) => counts.2 += 1,
+            }
+        }
+        counts
+    }
+
+    //和数据冲突的字: 修改或删除的字不存在或者笔画已经不是原来的笔画, 新增的字已经有不同的笔画
+    pub fn conflicts(&self, data:&[(char, Strokes)]) -> Vec<char>{
+        self.changes.iter().filter(|(ch, change)| {
+            let current = data.iter().find(|(c, _)| c == ch).map(|(_, strokes)| strokes);
+            match (change, current){
+                (Change::Added(strokes), Some(current)) => strokes != current,
+                (Change::Added(_), None) => false,
+                (Change::Modified(base, _), current) | (Change::Removed(base), current) => current != Some(base),
+            }
+        }).map(|(ch, _)| *ch).collect()
+    }
+
+    //应用到Vec<(char, Vec<Vec<(u16, u16)>>)>形式的数据上, 新增的字插入到字符顺序中对应的位置
+    //有冲突时返回错误, 数据不会被修改
+    pub fn apply(&self, data:&mut StrokeList) -> Result<(), String>{
+        let conflicts = self.conflicts(data);
+        if !conflicts.is_empty(){
+            return Err(format!("{}个字已经被修改过:{}", conflicts.len(), conflicts.iter().collect::<String>()));
+        }
+        for (ch, change) in &self.changes{
+            let index = data.iter().position(|(c, _)| c == ch);
+            match (change, index){
+                (Change::Added(strokes), Some(index)) | (Change::Modified(_, strokes), Some(index)) => {
+                    data[index].1 = strokes.clone();
+                }
+                (Change::Added(strokes), None) | (Change::Modified(_, strokes), None) => {
+                    let index = data.partition_point(|(c, _)| c < ch);
+                    data.insert(index, (*ch, strokes.clone()));
+                }
+                (Change::Removed(_), Some(index)) => {
+                    data.remove(index);
+                }
+                (Change::Removed(_), None) => ()
+            }
+        }
+        Ok(())
+    }
+}
+
+//STROKES.patch: 补丁后面附加审核信息的修改和新增的异体字关系
+pub fn write_patch(patch:&Patch, reviews:&ReviewChanges, variants:&Variants) -> Vec<u8>{
+    let mut data = serialize(patch).unwrap();
+    append_changes(&mut data, reviews);
+    append_variants(&mut data, variants);
+    data
+}
+
+pub fn read_patch(data:&[u8]) -> Result<(Patch, ReviewChanges, Variants), String>{
+    let patch: Patch = deserialize(data).map_err(|err| format!("补丁格式错误:{}", err))?;
+    let offset = serialized_size(&patch).unwrap() as usize;
+    let reviews = read_changes(data, offset)?;
+    let variants = read_variants(data, offset + changes_size(&reviews))?;
+    Ok((patch, reviews, variants))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::review::{Review, Reviews, Status, append_reviews, apply_changes, changed};
+    use crate::variant::Kind;
+
+    fn data() -> StrokeList{
+        vec![
+            ('一', vec![vec![(100, 500), (900, 500)]]),
+            ('二', vec![vec![(200, 300), (800, 300)], vec![(100, 700), (900, 700)]]),
+            ('十', vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]]),
+        ]
+    }
+
+    fn edited() -> (StrokeList, Patch){
+        let base = data();
+        let mut current: HashMap<char, Strokes> = base.iter().cloned().collect();
+        current.get_mut(&'一').unwrap()[0][1] = (880, 510);
+        current.remove(&'十');
+        current.insert('丁', vec![vec![(100, 200), (900, 200)], vec![(500, 200), (500, 900), (450, 850)]]);
+        let order = ['一', '二', '丁'];
+        let patch = Patch::diff(&base.into_iter().collect(), &current, &order);
+        let mut expected: StrokeList = current.into_iter().collect();
+        expected.sort_by_key(|(ch, _)| *ch);
+        (expected, patch)
+    }
+
+    #[test]
+    fn diff_lists_changes(){
+        let (_, patch) = edited();
+        assert_eq!(patch.counts(), (1, 1, 1));
+        let chars: Vec<char> = patch.changes.iter().map(|(ch, _)| *ch).collect();
+        assert_eq!(chars, vec!['一', '丁', '十']);
+    }
+
+    fn review(reviewer:&str) -> Review{
+        Review{ status: Status::Verified, reviewer: reviewer.to_string(), time: 1.0, notes: String::new() }
+    }
+
+    #[test]
+    fn round_trip(){
+        let (expected, patch) = edited();
+        //"一"修改了审核信息, "十"被删除
+        let mut base = Reviews::new();
+        base.insert('一', review("甲"));
+        base.insert('十', review("甲"));
+        let mut current = Reviews::new();
+        current.insert('一', review("乙"));
+        let reviews = changed(&base, &current);
+        assert_eq!(reviews, vec![('一', Some(review("乙"))), ('十', None)].into_iter().collect());
+        //新建"丁"时记录的异体字关系
+        let mut variants = Variants::default();
+        variants.link('丁', '十', Kind::Shape);
+        let (read, read_reviews, read_variants) = read_patch(&write_patch(&patch, &reviews, &variants)).unwrap();
+        assert_eq!(read, patch);
+        assert_eq!(read_reviews, reviews);
+        assert_eq!(read_variants, variants);
+        //没有审核信息时也能读取
+        assert_eq!(read_patch(&write_patch(&patch, &ReviewChanges::new(), &variants)).unwrap().2, variants);
+        apply_changes(&mut base, &read_reviews);
+        assert_eq!(base, current);
+        let mut list = data();
+        read.apply(&mut list).unwrap();
+        assert_eq!(list, expected);
+    }
+
+    #[test]
+    fn reads_old_patch_reviews(){
+        //旧的补丁后面附加的是Reviews
+        let (_, patch) = edited();
+        let mut reviews = Reviews::new();
+        reviews.insert('一', review("甲"));
+        let mut data = serialize(&patch).unwrap();
+        append_reviews(&mut data, &reviews);
+        let (_, read_reviews, variants) = read_patch(&data).unwrap();
+        assert_eq!(read_reviews, vec![('一', Some(review("甲")))].into_iter().collect());
+        assert!(variants.is_empty());
+    }
+
+    #[test]
+    fn conflicting_patch_is_rejected(){
+        let (_, patch) = edited();
+        //其他人先修改了"一"
+        let mut list = data();
+        list[0].1[0][0] = (120, 500);
+        let before = list.clone();
+        assert_eq!(patch.conflicts(&list), vec!['一']);
+        assert!(patch.apply(&mut list).is_err());
+        assert_eq!(list, before);
+        //同一个补丁不能应用两次
+        let mut list = data();
+        patch.apply(&mut list).unwrap();
+        assert!(patch.apply(&mut list).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+//康熙部首(按部首序号排列, 第1个是一部)
+const KANGXI_RADICALS: &str = "一丨丶丿乙亅二亠人儿入八冂冖冫几凵刀力勹匕匚匸十卜卩厂厶又口囗土士夂夊夕大女子宀寸小尢尸屮山巛工己巾干幺广廴廾弋弓彐彡彳心戈戶手支攴文斗斤方无日曰月木欠止歹殳毋比毛氏气水火爪父爻爿片牙牛犬玄玉瓜瓦甘生用田疋疒癶白皮皿目矛矢石示禸禾穴立竹米糸缶网羊羽老而耒耳聿肉臣自至臼舌舛舟艮色艸虍虫血行衣襾見角言谷豆豕豸貝赤走足身車辛辰辵邑酉釆里金長門阜隶隹雨靑非面革韋韭音頁風飛食首香馬骨高髟鬥鬯鬲鬼魚鳥鹵鹿麥麻黃黍黑黹黽鼎鼓鼠鼻齊齒龍龜龠";
+
+//常用的部首变体和简化字部首: (写法, 部首序号)
+const RADICAL_VARIANTS: [(char, u8); 24] = [
+    ('亻', 9), ('刂', 18), ('⺌', 42), ('彑', 58), ('忄', 61), ('扌', 64), ('攵', 66), ('氵', 85),
+    ('灬', 86), ('犭', 94), ('王', 96), ('罒', 122), ('礻', 113), ('纟', 120), ('艹', 140), ('衤', 145),
+    ('讠', 149), ('贝', 154), ('车', 159), ('辶', 162), ('钅', 167), ('门', 169), ('阝', 170), ('饣', 184),
+];
+
+//部首的序号(1~214)
+pub fn radical_number(ch: char) -> Option<u8>{
+    if let Some(index) = KANGXI_RADICALS.chars().position(|c| c == ch){
+        return Some(index as u8 + 1);
+    }
+    RADICAL_VARIANTS.iter().find(|(c, _)| *c == ch).map(|(_, number)| *number)
+}
+
+//去掉声调, 统一为小写, ü写作v
+pub fn normalize_pinyin(pinyin:&str) -> String{
+    let mut result = String::new();
+    for ch in pinyin.to_lowercase().replace("u:", "v").chars(){
+        let base = match ch{
+            'ā' | 'á' | 'ǎ' | 'à' => 'a',
+            'ē' | 'é' | 'ě' | 'è' => 'e',
+            'ī' | 'í' | 'ǐ' | 'ì' => 'i',
+            'ō' | 'ó' | 'ǒ' | 'ò' => 'o',
+            'ū' | 'ú' | 'ǔ' | 'ù' => 'u',
+            'ǖ' | 'ǘ' | 'ǚ' | 'ǜ' | 'ü' => 'v',
+            'ń' | 'ň' | 'ǹ' => 'n',
+            'ḿ' => 'm',
+            '1'..='5' => continue,
+            _ => ch
+        };
+        result.push(base);
+    }
+    result
+}
+
+//是否是拼音查询: 去掉声调后只有字母和数字声调(shui、shui3、shuǐ、lu:4、nǚ)
+pub fn is_pinyin_query(text:&str) -> bool{
+    let normalized = normalize_pinyin(text.trim());
+    normalized.chars().any(|ch| ch.is_ascii_lowercase()) && normalized.chars().all(|ch| ch.is_ascii_alphanumeric())
+}
+
+//部首查询: "85"、"85.4"、"85+4"、"水+4"、"氵4"
+pub fn parse_radical_query(query:&str) -> Option<(u8, Option<i8>)>{
+    let query = query.trim();
+    let first = query.chars().next()?;
+    let (radical, rest) = if first.is_ascii_digit(){
+        let end = query.find(|c: char| !c.is_ascii_digit()).unwrap_or(query.len());
+        (query[..end].parse().ok()?, &query[end..])
+    }else{
+        (radical_number(first)?, &query[first.len_utf8()..])
+    };
+    if radical == 0 || radical > 214{
+        return None;
+    }
+    let rest = rest.trim_start_matches(['.', '+', ' ']);
+    if rest.is_empty(){
+        return Some((radical, None));
+    }
+    Some((radical, Some(rest.parse().ok()?)))
+}
+
+//读音和部首表
+#[derive(Default)]
+pub struct Readings{
+    //不带声调的拼音对应的字
+    pinyin: HashMap<String, Vec<char>>,
+    //字对应的(部首序号, 除部首外的笔画数)
+    radicals: HashMap<char, Vec<(u8, i8)>>,
+}
+
+//"U+6C34"
+fn parse_code_point(text:&str) -> Option<char>{
+    u32::from_str_radix(text.strip_prefix("U+")?, 16).ok().and_then(char::from_u32)
+}
+
+impl Readings{
+    pub fn is_empty(&self) -> bool{
+        self.pinyin.is_empty() && self.radicals.is_empty()
+    }
+
+    pub fn has_pinyin(&self) -> bool{
+        !self.pinyin.is_empty()
+    }
+
+    pub fn has_radicals(&self) -> bool{
+        !self.radicals.is_empty()
+    }
+
+    fn add_pinyin(&mut self, ch: char, pinyin:&str){
+        let chars = self.pinyin.entry(normalize_pinyin(pinyin)).or_default();
+        if !chars.contains(&ch){
+            chars.push(ch);
+        }
+    }
+
+    //Unihan数据库的文本文件(Unihan_Readings.txt中的kMandarin、kHanyuPinyin, Unihan_IRGSources.txt中的kRSUnicode)
+    pub fn add_unihan(&mut self, text:&str){
+        for line in text.lines(){
+            if line.starts_with('#'){
+                continue;
+            }
+            let mut fields = line.splitn(3, '\t');
+            let (ch, field, value) = match (fields.next().and_then(parse_code_point), fields.next(), fields.next()){
+                (Some(ch), Some(field), Some(value)) => (ch, field, value),
+                _ => continue
+            };
+            match field{
+                "kMandarin" => {
+                    for pinyin in value.split_whitespace(){
+                        self.add_pinyin(ch, pinyin);
+                    }
+                }
+                //"10019.020:tiān"
+                "kHanyuPinyin" => {
+                    for entry in value.split_whitespace(){
+                        if let Some((_, readings)) = entry.split_once(':'){
+                            for pinyin in readings.split(','){
+                                self.add_pinyin(ch, pinyin);
+                            }
+                        }
+                    }
+                }
+                //"85.4", 简化字部首写作"85'.4"
+                "kRSUnicode" => {
+                    for entry in value.split_whitespace(){
+                        if let Some((radical, residual)) = entry.split_once('.'){
+                            let radical = radical.trim_end_matches(['\'', '"']);
+                            if let (Ok(radical), Ok(residual)) = (radical.parse(), residual.parse()){
+                                let radicals = self.radicals.entry(ch).or_default();
+                                if !radicals.contains(&(radical, residual)){
+                                    radicals.push((radical, residual));
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    //CC-CEDICT词典(cedict_ts.u8), 只使用单字的词条: "傳 传 [chuan2] /.../"
+    pub fn add_cedict(&mut self, text:&str){
+        for line in text.lines(){
+            if line.starts_with('#'){
+                continue;
+            }
+            let (words, rest) = match line.split_once(" ["){
+                Some(parts) => parts,
+                None => continue
+            };
+            let pinyin = match rest.split_once(']'){
+                Some((pinyin, _)) if !pinyin.contains(' ') => pinyin,
+                _ => continue
+            };
+            for word in words.split(' '){
+                let mut chars = word.chars();
+                if let (Some(ch), None) = (chars.next(), chars.next()){
+                    self.add_pinyin(ch, pinyin);
+                }
+            }
+        }
+    }
+
+    //按拼音查找, 可以带声调或者数字声调
+    pub fn by_pinyin(&self, pinyin:&str) -> Vec<char>{
+        self.pinyin.get(&normalize_pinyin(pinyin.trim())).cloned().unwrap_or_default()
+    }
+
+    //按部首和除部首外的笔画数查找, 按笔画数排序
+    pub fn by_radical(&self, radical: u8, residual: Option<i8>) -> Vec<char>{
+        let mut chars: Vec<(i8, char)> = self.radicals.iter().filter_map(|(ch, radicals)| {
+            radicals.iter().find(|(r, s)| *r == radical && residual.map(|residual| residual == *s).unwrap_or(true))
+                .map(|(_, s)| (*s, *ch))
+        }).collect();
+        chars.sort_unstable();
+        chars.into_iter().map(|(_, ch)| ch).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const UNIHAN: &str = "# Unihan_Readings.txt
+U+6C34\tkMandarin\tshuǐ
+U+6C34\tkRSUnicode\t85.0
+U+6C5F\tkMandarin\tjiāng
+U+6C5F\tkHanyuPinyin\t42008.010:jiāng
+U+6C5F\tkRSUnicode\t85.3
+U+6CB3\tkRSUnicode\t85.5
+U+8BF4\tkMandarin\tshuō
+U+8BF4\tkRSUnicode\t149'.7
+bad line
+";
+
+    #[test]
+    fn normalizes_pinyin(){
+        assert_eq!(normalize_pinyin("Shuǐ"), "shui");
+        assert_eq!(normalize_pinyin("lu:4"), "lv");
+        assert_eq!(normalize_pinyin("nǚ"), "nv");
+        assert_eq!(normalize_pinyin("jiang1"), "jiang");
+    }
+
+    #[test]
+    fn detects_pinyin_queries(){
+        assert!(is_pinyin_query("shui"));
+        assert!(is_pinyin_query("shui3"));
+        assert!(is_pinyin_query("shuǐ"));
+        assert!(is_pinyin_query("SHUǏ"));
+        assert!(is_pinyin_query("lu:4"));
+        assert!(is_pinyin_query("nǚ"));
+        assert!(!is_pinyin_query("水"));
+        assert!(!is_pinyin_query("shui水"));
+        assert!(!is_pinyin_query("85"));
+        assert!(!is_pinyin_query(""));
+    }
+
+    #[test]
+    fn parses_radical_queries(){
+        assert_eq!(radical_number('一'), Some(1));
+        assert_eq!(radical_number('水'), Some(85));
+        assert_eq!(radical_number('氵'), Some(85));
+        assert_eq!(radical_number('a'), None);
+        assert_eq!(parse_radical_query("85"), Some((85, None)));
+        assert_eq!(parse_radical_query("85.4"), Some((85, Some(4))));
+        assert_eq!(parse_radical_query(" 85+4 "), Some((85, Some(4))));
+        assert_eq!(parse_radical_query("水+4"), Some((85, Some(4))));
+        assert_eq!(parse_radical_query("氵4"), Some((85, Some(4))));
+        assert_eq!(parse_radical_query("0"), None);
+        assert_eq!(parse_radical_query("215"), None);
+        assert_eq!(parse_radical_query("85x"), None);
+        assert_eq!(parse_radical_query(""), None);
+    }
+
+    #[test]
+    fn reads_unihan(){
+        let mut readings = Readings::default();
+        assert!(readings.is_empty());
+        readings.add_unihan(UNIHAN);
+        assert!(readings.has_pinyin() && readings.has_radicals());
+        assert_eq!(readings.by_pinyin("shui3"), vec!['水']);
+        //kMandarin和kHanyuPinyin重复的读音只记录一次
+        assert_eq!(readings.by_pinyin("jiāng"), vec!['江']);
+        assert_eq!(readings.by_radical(85, None), vec!['水', '江', '河']);
+        assert_eq!(readings.by_radical(85, Some(3)), vec!['江']);
+        //简化字部首
+        assert_eq!(readings.by_radical(149, Some(7)), vec!['说']);
+    }
+
+    #[test]
+    fn reads_cedict(){
+        let mut readings = Readings::default();
+        readings.add_cedict("# CC-CEDICT\n傳 传 [chuan2] /to pass on/\n傳說 传说 [chuan2 shuo1] /legend/\n女 女 [nu:3] /female/\n");
+        assert_eq!(readings.by_pinyin("chuan"), vec!['傳', '传']);
+        assert_eq!(readings.by_pinyin("nv3"), vec!['女']);
+        //词语不作为单字的读音
+        assert!(readings.by_pinyin("shuo").is_empty());
+        assert!(!readings.has_radicals());
+    }
+}
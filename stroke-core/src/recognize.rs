@@ -0,0 +1,165 @@
+use crate::data::Strokes;
+use crate::matching::{normalize, resample, to_points};
+
+//识别时每一笔采样的点数(比比较一笔时少, 识别时要和所有字比较)
+const SAMPLES: usize = 12;
+//笔画数相差超过这个数的字不比较
+const MAX_STROKE_DIFFERENCE: usize = 2;
+//方向相反时增加的距离(归一化坐标)
+const DIRECTION_WEIGHT: f64 = 0.3;
+//多写或者少写一笔的距离
+const MISSING_COST: f64 = 0.5;
+//笔顺每错一对增加的距离
+const ORDER_PENALTY: f64 = 0.02;
+
+//一个候选的字
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate{
+    pub ch: char,
+    //平均每一笔的距离(越小越像)
+    pub score: f64,
+    //笔顺错的对数
+    pub order_errors: usize,
+}
+
+//一个字的笔画: 整个字归一化之后重新采样
+pub(crate) type Shape = Vec<Vec<(f64, f64)>>;
+
+//把整个字移到原点并缩放到单位大小, 保持笔画之间的位置关系
+pub(crate) fn shape(strokes:&Strokes) -> Shape{
+    let all: Vec<(f64, f64)> = strokes.iter().flat_map(|stroke| to_points(stroke)).collect();
+    let normalized = normalize(&all);
+    let mut start = 0;
+    strokes.iter().map(|stroke| {
+        let points = &normalized[start..start + stroke.len()];
+        start += stroke.len();
+        resample(points, SAMPLES)
+    }).collect()
+}
+
+//两笔的距离: 对应点的平均距离, 方向相反时增加, 没有点的笔画按少写了一笔计算
+pub(crate) fn stroke_cost(a:&[(f64, f64)], b:&[(f64, f64)]) -> f64{
+    if a.is_empty() || b.is_empty(){
+        return MISSING_COST;
+    }
+    let mean = a.iter().zip(b.iter()).map(|(p, q)| (p.0 - q.0).hypot(p.1 - q.1)).sum::<f64>() / a.len().max(1) as f64;
+    let (da, db) = ((a[a.len() - 1].0 - a[0].0, a[a.len() - 1].1 - a[0].1), (b[b.len() - 1].0 - b[0].0, b[b.len() - 1].1 - b[0].1));
+    let (la, lb) = (da.0.hypot(da.1), db.0.hypot(db.1));
+    //浮点误差可能使余弦略大于1
+    let direction = if la > 0.0 && lb > 0.0 { ((da.0 * db.0 + da.1 * db.1) / (la * lb)).clamp(-1.0, 1.0) } else { 1.0 };
+    mean + DIRECTION_WEIGHT * (1.0 - direction) / 2.0
+}
+
+//按距离从小到大贪心地配对写的笔画和字的笔画(允许笔顺错误), 返回(总距离, 笔顺错的对数)
+fn match_shapes(drawn:&Shape, candidate:&Shape) -> (f64, usize){
+    let mut costs = vec![];
+    for (i, a) in drawn.iter().enumerate(){
+        for (j, b) in candidate.iter().enumerate(){
+            costs.push((stroke_cost(a, b), i, j));
+        }
+    }
+    costs.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let (mut used_drawn, mut used_candidate) = (vec![false; drawn.len()], vec![false; candidate.len()]);
+    let mut pairs = vec![];
+    let mut total = 0.0;
+    for (cost, i, j) in costs{
+        if !used_drawn[i] && !used_candidate[j]{
+            used_drawn[i] = true;
+            used_candidate[j] = true;
+            pairs.push((i, j));
+            total += cost;
+        }
+    }
+    let missing = drawn.len().max(candidate.len()) - pairs.len();
+    total += missing as f64 * MISSING_COST;
+    //写的顺序和字的笔顺不一致的对数
+    pairs.sort_unstable();
+    let mut order_errors = 0;
+    for (k, (_, a)) in pairs.iter().enumerate(){
+        order_errors += pairs[k + 1..].iter().filter(|(_, b)| b < a).count();
+    }
+    (total, order_errors)
+}
+
+//预先处理好的数据中所有的字
+pub struct Recognizer{
+    shapes: Vec<(char, Shape)>,
+}
+
+impl Recognizer{
+    pub fn new<'a, I: IntoIterator<Item = (char, &'a Strokes)>>(strokes: I) -> Recognizer{
+        let mut shapes: Vec<(char, Shape)> = strokes.into_iter()
+            .filter(|(_, strokes)| !strokes.is_empty())
+            .map(|(ch, strokes)| (ch, shape(strokes)))
+            .collect();
+        shapes.sort_by_key(|(ch, _)| *ch);
+        Recognizer{ shapes }
+    }
+
+    //最像的limit个字, 按距离从小到大排列
+    pub fn recognize(&self, drawn:&Strokes, limit: usize) -> Vec<Candidate>{
+        if drawn.is_empty(){
+            return vec![];
+        }
+        let drawn = shape(drawn);
+        let mut candidates: Vec<Candidate> = self.shapes.iter()
+            .filter(|(_, shape)| shape.len().abs_diff(drawn.len()) <= MAX_STROKE_DIFFERENCE)
+            .map(|(ch, shape)| {
+                let (total, order_errors) = match_shapes(&drawn, shape);
+                let score = ((total + order_errors as f64 * ORDER_PENALTY) / drawn.len().max(shape.len()) as f64).max(0.0);
+                Candidate{ ch: *ch, score, order_errors }
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+        candidates.truncate(limit);
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn data() -> Vec<(char, Strokes)>{
+        vec![
+            ('一', vec![vec![(100, 500), (900, 500)]]),
+            ('二', vec![vec![(200, 300), (800, 300)], vec![(100, 700), (900, 700)]]),
+            ('十', vec![vec![(100, 500), (900, 500)], vec![(500, 100), (500, 900)]]),
+            ('丁', vec![vec![(100, 200), (900, 200)], vec![(500, 200), (500, 900), (450, 850)]]),
+            ('空', vec![]),
+        ]
+    }
+
+    fn recognizer() -> Recognizer{
+        let data = data();
+        Recognizer::new(data.iter().map(|(ch, strokes)| (*ch, strokes)))
+    }
+
+    #[test]
+    fn recognizes_exact_char(){
+        let candidates = recognizer().recognize(&data()[2].1, 10);
+        assert_eq!(candidates[0].ch, '十');
+        assert_eq!(candidates[0].score, 0.0);
+        assert_eq!(candidates[0].order_errors, 0);
+        //没有笔画的字不参加识别
+        assert!(candidates.iter().all(|candidate| candidate.ch != '空'));
+        assert_eq!(recognizer().recognize(&data()[2].1, 2).len(), 2);
+    }
+
+    #[test]
+    fn wrong_order_is_counted(){
+        let candidates = recognizer().recognize(&vec![vec![(500, 100), (500, 900)], vec![(100, 500), (900, 500)]], 1);
+        assert_eq!(candidates[0].ch, '十');
+        assert_eq!(candidates[0].order_errors, 1);
+    }
+
+    #[test]
+    fn empty_strokes_do_not_panic(){
+        let recognizer = recognizer();
+        assert!(recognizer.recognize(&vec![], 10).is_empty());
+        let candidates = recognizer.recognize(&vec![vec![], vec![(100, 500), (900, 500)]], 10);
+        assert!(!candidates.is_empty());
+        assert!(candidates.iter().all(|candidate| candidate.score >= 0.0 && candidate.score.is_finite()));
+        assert_eq!(stroke_cost(&[], &[(0.0, 0.0)]), MISSING_COST);
+    }
+}
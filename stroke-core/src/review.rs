@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use bincode::{deserialize, serialize, serialized_size};
+
+//附加在数据后面的审核信息的标记(bincode读取时会忽略后面多余的数据, 旧版本仍然可以读取)
+const MAGIC: &[u8] = b"REVIEWS1";
+
+//审核状态
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Status{
+    #[default]
+    Unreviewed,
+    Verified,
+    NeedsWork,
+}
+
+impl Status{
+    pub const ALL: [Status; 3] = [Status::Unreviewed, Status::Verified, Status::NeedsWork];
+
+    //页面中使用的名字(option的value和class)
+    pub fn id(&self) -> &'static str{
+        match self{
+            Status::Unreviewed => "unreviewed",
+            Status::Verified => "verified",
+            Status::NeedsWork => "needs_work",
+        }
+    }
+
+    pub fn from_id(id:&str) -> Option<Status>{
+        Status::ALL.iter().find(|status| status.id() == id).cloned()
+    }
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            Status::Unreviewed => "未审核",
+            Status::Verified => "已确认",
+            Status::NeedsWork => "需要修改",
+        }
+    }
+}
+
+//一个字的审核信息
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Review{
+    pub status: Status,
+    pub reviewer: String,
+    //修改时间(毫秒)
+    pub time: f64,
+    pub notes: String,
+}
+
+pub type Reviews = BTreeMap<char, Review>;
+
+//读取offset(前面数据的长度)之后附加的审核信息, 没有时返回空
+pub fn read_reviews(data:&[u8], offset: usize) -> Result<Reviews, String>{
+    match data.get(offset..){
+        Some(rest) if rest.starts_with(MAGIC) => deserialize(&rest[MAGIC.len()..]).map_err(|err| format!("审核信息格式错误:{}", err)),
+        _ => Ok(Reviews::new())
+    }
+}
+
+//在数据后面附加审核信息
+pub fn append_reviews(data:&mut Vec<u8>, reviews:&Reviews){
+    if reviews.is_empty(){
+        return;
+    }
+    data.extend_from_slice(MAGIC);
+    data.extend(serialize(reviews).unwrap());
+}
+
+//附加的审核信息的长度, 后面是附加的异体字关系
+pub fn reviews_size(reviews:&Reviews) -> usize{
+    if reviews.is_empty() { 0 } else { MAGIC.len() + serialized_size(reviews).unwrap() as usize }
+}
+
+//补丁中审核信息的修改, None表示删除了这个字的审核信息(删除或者改名的字)
+pub type ReviewChanges = BTreeMap<char, Option<Review>>;
+
+//补丁后面附加的审核信息修改的标记, 旧的补丁使用MAGIC(只有新增和修改)
+const CHANGES_MAGIC: &[u8] = b"REVIEWS2";
+
+//current中和base不同的审核信息, 包括删除的
+pub fn changed(base:&Reviews, current:&Reviews) -> ReviewChanges{
+    let mut changes: ReviewChanges = current.iter().filter(|(ch, review)| base.get(ch) != Some(review))
+        .map(|(ch, review)| (*ch, Some(review.clone()))).collect();
+    changes.extend(base.keys().filter(|ch| !current.contains_key(ch)).map(|ch| (*ch, None)));
+    changes
+}
+
+//应用审核信息的修改
+pub fn apply_changes(reviews:&mut Reviews, changes:&ReviewChanges){
+    for (ch, change) in changes{
+        match change{
+            Some(review) => {
+                reviews.insert(*ch, review.clone());
+            }
+            None => {
+                reviews.remove(ch);
+            }
+        }
+    }
+}
+
+//三方合并审核信息: 在ours的基础上应用theirs相对base的修改, 两边都修改了的字保留ours
+pub fn merge_reviews(base:&Reviews, ours:&Reviews, theirs:&Reviews) -> Reviews{
+    let ours_changes = changed(base, ours);
+    let theirs_changes: ReviewChanges = changed(base, theirs).into_iter()
+        .filter(|(ch, _)| !ours_changes.contains_key(ch))
+        .collect();
+    let mut merged = ours.clone();
+    apply_changes(&mut merged, &theirs_changes);
+    merged
+}
+
+//在补丁后面附加审核信息的修改
+pub fn append_changes(data:&mut Vec<u8>, changes:&ReviewChanges){
+    if changes.is_empty(){
+        return;
+    }
+    data.extend_from_slice(CHANGES_MAGIC);
+    data.extend(serialize(changes).unwrap());
+}
+
+//补丁后面附加的审核信息修改的长度
+pub fn changes_size(changes:&ReviewChanges) -> usize{
+    if changes.is_empty() { 0 } else { CHANGES_MAGIC.len() + serialized_size(changes).unwrap() as usize }
+}
+
+//读取补丁后面的审核信息的修改, 也可以读取旧的补丁
+pub fn read_changes(data:&[u8], offset: usize) -> Result<ReviewChanges, String>{
+    match data.get(offset..){
+        Some(rest) if rest.starts_with(CHANGES_MAGIC) => deserialize(&rest[CHANGES_MAGIC.len()..]).map_err(|err| format!("审核信息格式错误:{}", err)),
+        _ => Ok(read_reviews(data, offset)?.into_iter().map(|(ch, review)| (ch, Some(review))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn review(status: Status, reviewer:&str) -> Review{
+        Review{ status, reviewer: reviewer.to_string(), time: 1.0, notes: String::new() }
+    }
+
+    #[test]
+    fn merges_both_sides(){
+        let mut base = Reviews::new();
+        base.insert('一', review(Status::Unreviewed, "甲"));
+        base.insert('二', review(Status::Unreviewed, "甲"));
+        base.insert('十', review(Status::Unreviewed, "甲"));
+        //我们确认了"一", 删除了"十"
+        let mut ours = base.clone();
+        ours.insert('一', review(Status::Verified, "乙"));
+        ours.remove(&'十');
+        //他们也修改了"一", 标记了"二", 新增了"丁"
+        let mut theirs = base.clone();
+        theirs.insert('一', review(Status::NeedsWork, "丙"));
+        theirs.insert('二', review(Status::NeedsWork, "丙"));
+        theirs.insert('丁', review(Status::Verified, "丙"));
+        let merged = merge_reviews(&base, &ours, &theirs);
+        let expected: Reviews = vec![
+            ('一', review(Status::Verified, "乙")),
+            ('丁', review(Status::Verified, "丙")),
+            ('二', review(Status::NeedsWork, "丙")),
+        ].into_iter().collect();
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn sizes_match_appended_data(){
+        let mut reviews = Reviews::new();
+        assert_eq!(reviews_size(&reviews), 0);
+        reviews.insert('一', review(Status::Verified, "甲"));
+        let mut data = vec![];
+        append_reviews(&mut data, &reviews);
+        assert_eq!(reviews_size(&reviews), data.len());
+        assert_eq!(read_reviews(&data, 0).unwrap(), reviews);
+        let changes = changed(&Reviews::new(), &reviews);
+        let mut data = vec![];
+        append_changes(&mut data, &changes);
+        assert_eq!(changes_size(&changes), data.len());
+    }
+}
@@ -0,0 +1,380 @@
+use std::f64::consts::PI;
+use crate::data::{Strokes, problems};
+
+//文字的种类, 每种文字有自己的参考字体、坐标规范和笔画规则
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Script{
+    #[default]
+    Han,
+    Kana,
+    Hangul,
+    Latin,
+}
+
+//笔画的类型, 按笔画的形状和方向(画布坐标, y向下)粗略分类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrokeKind{
+    Dot,
+    //横
+    Horizontal,
+    //竖
+    Vertical,
+    //撇(向左下)
+    Falling,
+    //捺(向右下)
+    Pressing,
+    //提(向右上)
+    Rising,
+    //从右向左或者从下向上
+    Backward,
+    //有转折
+    Turning,
+    Curve,
+    //首尾相接的圈
+    Loop,
+}
+
+impl StrokeKind{
+    pub fn name(&self) -> &'static str{
+        match self{
+            StrokeKind::Dot => "点",
+            StrokeKind::Horizontal => "横",
+            StrokeKind::Vertical => "竖",
+            StrokeKind::Falling => "撇",
+            StrokeKind::Pressing => "捺",
+            StrokeKind::Rising => "提",
+            StrokeKind::Backward => "反向的笔画",
+            StrokeKind::Turning => "折",
+            StrokeKind::Curve => "曲线",
+            StrokeKind::Loop => "圈",
+        }
+    }
+}
+
+//短于这个长度(字形坐标)的笔画是点
+const DOT_LENGTH: f64 = 80.0;
+//方向变化超过这个角度(弧度)认为是转折
+const TURN_ANGLE: f64 = PI / 3.0;
+//方向变化超过这个角度(弧度)是尖角
+const SHARP_ANGLE: f64 = PI * 2.0 / 3.0;
+//圈转过的总角度(弧度)至少是这个角度
+const LOOP_ANGLE: f64 = PI * 1.5;
+//首尾距离小于总长度的这个比例认为是圈
+const LOOP_RATIO: f64 = 0.2;
+//首尾距离小于总长度的这个比例认为是曲线
+const CURVE_RATIO: f64 = 0.85;
+
+pub fn classify(stroke:&[(u16, u16)]) -> StrokeKind{
+    let points: Vec<(f64, f64)> = stroke.iter().map(|(x, y)| (*x as f64, *y as f64)).collect();
+    let length: f64 = points.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).sum();
+    if points.len() < 2 || length < DOT_LENGTH{
+        return StrokeKind::Dot;
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (dx, dy) = (last.0 - first.0, last.1 - first.1);
+    let chord = dx.hypot(dy);
+    //相邻线段之间最大的方向变化, 忽略很短的线段, 避免抖动被当作转折
+    let segments: Vec<(f64, f64)> = points.windows(2)
+        .map(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1))
+        .filter(|(x, y)| x.hypot(*y) >= DOT_LENGTH / 4.0)
+        .collect();
+    let turns: Vec<f64> = segments.windows(2).map(|w| {
+        let angle = (w[1].1.atan2(w[1].0) - w[0].1.atan2(w[0].0)).abs();
+        if angle > PI { 2.0 * PI - angle } else { angle }
+    }).collect();
+    let max_turn = turns.iter().cloned().fold(0.0, f64::max);
+    //圈是逐渐转弯并且转过大半周的, 有尖角折回来的是折
+    if chord < length * LOOP_RATIO && max_turn < SHARP_ANGLE && turns.iter().sum::<f64>() > LOOP_ANGLE{
+        return StrokeKind::Loop;
+    }
+    if max_turn > TURN_ANGLE{
+        return StrokeKind::Turning;
+    }
+    if chord < length * CURVE_RATIO{
+        return StrokeKind::Curve;
+    }
+    if dx > 0.0 && dy.abs() < dx * 0.3{
+        StrokeKind::Horizontal
+    }else if dy > 0.0 && dx.abs() < dy * 0.3{
+        StrokeKind::Vertical
+    }else if dy > 0.0{
+        //平撇也是向左下的
+        if dx < 0.0 { StrokeKind::Falling } else { StrokeKind::Pressing }
+    }else if dx > 0.0{
+        StrokeKind::Rising
+    }else{
+        StrokeKind::Backward
+    }
+}
+
+impl Script{
+    pub const ALL: [Script; 4] = [Script::Han, Script::Kana, Script::Hangul, Script::Latin];
+
+    pub fn id(&self) -> &'static str{
+        match self{
+            Script::Han => "han",
+            Script::Kana => "kana",
+            Script::Hangul => "hangul",
+            Script::Latin => "latin",
+        }
+    }
+
+    pub fn from_id(id:&str) -> Option<Script>{
+        Script::ALL.iter().find(|script| script.id() == id).cloned()
+    }
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            Script::Han => "汉字",
+            Script::Kana => "假名",
+            Script::Hangul => "谚文",
+            Script::Latin => "拉丁字母",
+        }
+    }
+
+    //参考字体(CSS font-family), 第一个字体是页面中用@font-face加载的html目录中的字体文件
+    pub fn font(&self) -> &'static str{
+        match self{
+            Script::Han => "楷体_GB2312",
+            Script::Kana => "kana_reference, 'UD Digi Kyokasho N-R', Klee, 'Hiragino Mincho ProN', serif",
+            Script::Hangul => "hangul_reference, Batang, 'Nanum Myeongjo', serif",
+            Script::Latin => "latin_reference, 'Segoe Script', 'Brush Script MT', cursive",
+        }
+    }
+
+    //参考字体的基线(字形坐标y)
+    pub fn baseline(&self) -> f64{
+        match self{
+            Script::Latin => 700.0,
+            _ => 750.0,
+        }
+    }
+
+    //书写格的横线(字形坐标y): 拉丁字母是上伸线、x高度线、基线、下伸线的四线格, 其他文字是中线
+    pub fn guides(&self) -> &'static [f64]{
+        match self{
+            Script::Han => &[],
+            Script::Kana | Script::Hangul => &[500.0],
+            Script::Latin => &[150.0, 400.0, 700.0, 900.0],
+        }
+    }
+
+    //一个字最多的笔画数
+    pub fn max_strokes(&self) -> usize{
+        match self{
+            Script::Han => 64,
+            //包括浊音符号和半浊音符号
+            Script::Kana => 6,
+            //一个音节最多由四个字母组成
+            Script::Hangul => 20,
+            Script::Latin => 4,
+        }
+    }
+
+    //不应该出现的笔画类型
+    fn forbidden(&self) -> &'static [StrokeKind]{
+        match self{
+            Script::Han => &[StrokeKind::Loop, StrokeKind::Backward],
+            Script::Hangul => &[StrokeKind::Backward],
+            Script::Kana | Script::Latin => &[],
+        }
+    }
+
+    //导出HashMap格式时的文件名
+    pub fn map_file(&self) -> String{
+        match self{
+            Script::Han => "gb2312.data".to_string(),
+            script => format!("{}.data", script.id()),
+        }
+    }
+}
+
+//字所属的文字(按Unicode范围判断), 数字和标点等各种文字都使用的字符返回None
+pub fn script(ch: char) -> Option<Script>{
+    match ch as u32{
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => Some(Script::Kana),
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xA960..=0xA97F | 0xAC00..=0xD7A3 | 0xD7B0..=0xD7FF => Some(Script::Hangul),
+        0x00C0..=0x024F => Some(Script::Latin),
+        _ if ch.is_ascii_alphabetic() => Some(Script::Latin),
+        _ if ch.is_alphabetic() => Some(Script::Han),
+        _ => None,
+    }
+}
+
+//数据中最多的文字(不计算数字和标点), 没有字时是汉字
+pub fn majority(chars:&[char]) -> Script{
+    let mut counts = [0; 4];
+    for script in chars.iter().filter_map(|ch| script(*ch)){
+        counts[Script::ALL.iter().position(|s| *s == script).unwrap()] += 1;
+    }
+    let index = (0..counts.len()).max_by_key(|i| (counts[*i], std::cmp::Reverse(*i))).unwrap();
+    Script::ALL[index]
+}
+
+//一个数据使用的文字: 指定了文字时整个数据都使用这种文字的字体、坐标规范和笔画规则
+//自动(fixed为None)时按每个字的Unicode范围判断, 数字和标点按数据中最多的文字处理
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DataScript{
+    pub fixed: Option<Script>,
+    pub majority: Script,
+}
+
+impl DataScript{
+    pub fn detect(chars:&[char], fixed: Option<Script>) -> DataScript{
+        DataScript{ fixed, majority: majority(chars) }
+    }
+
+    //字按哪种文字处理
+    pub fn of(&self, ch: char) -> Script{
+        self.fixed.or_else(|| script(ch)).unwrap_or(self.majority)
+    }
+
+    //数据的主要文字
+    pub fn main(&self) -> Script{
+        self.fixed.unwrap_or(self.majority)
+    }
+}
+
+//按照文字的规则检查笔画数和笔画类型
+pub fn rules(script: Script, strokes:&Strokes) -> Vec<String>{
+    let mut problems = vec![];
+    if strokes.len() > script.max_strokes(){
+        problems.push(format!("{}笔超过{}的上限{}笔", strokes.len(), script.name(), script.max_strokes()));
+    }
+    for (i, stroke) in strokes.iter().enumerate(){
+        let kind = classify(stroke);
+        if script.forbidden().contains(&kind){
+            problems.push(format!("第{}笔是{}, {}中不应该出现", i + 1, kind.name(), script.name()));
+        }
+    }
+    problems
+}
+
+//data::problems中的问题以及不符合文字规则的地方
+pub fn lint(script: Script, strokes:&Strokes) -> Vec<String>{
+    let mut problems = problems(strokes);
+    problems.extend(rules(script, strokes));
+    problems
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    //(说明, 笔画, 期望的类型)
+    type Case = (&'static str, Vec<(u16, u16)>, StrokeKind);
+
+    //圆心(500, 500)半径300, 从右边开始顺时针(画布坐标)画degrees度的弧
+    fn arc(degrees: f64, segments: usize) -> Vec<(u16, u16)>{
+        (0..=segments).map(|i| {
+            let angle = (degrees * i as f64 / segments as f64).to_radians();
+            ((500.0 + 300.0 * angle.cos()).round() as u16, (500.0 + 300.0 * angle.sin()).round() as u16)
+        }).collect()
+    }
+
+    //三段长300的线段, 每次转过degrees度
+    fn bend(degrees: f64) -> Vec<(u16, u16)>{
+        let mut points = vec![(100.0, 300.0)];
+        for i in 0..3{
+            let (x, y) = points[i];
+            let angle = (degrees * i as f64).to_radians();
+            points.push((x + 300.0 * angle.cos(), y + 300.0 * angle.sin()));
+        }
+        points.into_iter().map(|(x, y)| (x.round() as u16, y.round() as u16)).collect()
+    }
+
+    #[test]
+    fn classifies_each_kind(){
+        let cases: Vec<Case> = vec![
+            ("一个点", vec![(500, 500)], StrokeKind::Dot),
+            ("点", vec![(500, 500), (540, 560)], StrokeKind::Dot),
+            ("横", vec![(100, 500), (900, 520)], StrokeKind::Horizontal),
+            ("竖", vec![(500, 100), (510, 900)], StrokeKind::Vertical),
+            ("撇", vec![(600, 100), (200, 700)], StrokeKind::Falling),
+            ("捺", vec![(200, 100), (700, 700)], StrokeKind::Pressing),
+            ("提", vec![(200, 700), (600, 500)], StrokeKind::Rising),
+            ("从右向左", vec![(900, 500), (100, 500)], StrokeKind::Backward),
+            ("从下向上", vec![(500, 900), (500, 100)], StrokeKind::Backward),
+            ("横折", vec![(100, 200), (800, 200), (800, 800)], StrokeKind::Turning),
+            ("半圆", arc(180.0, 12), StrokeKind::Curve),
+            ("圈", arc(345.0, 24), StrokeKind::Loop),
+            //尖角折回来的不是圈
+            ("折回", vec![(100, 100), (800, 100), (120, 130)], StrokeKind::Turning),
+        ];
+        for (name, stroke, kind) in cases{
+            assert_eq!(classify(&stroke), kind, "{}", name);
+        }
+    }
+
+    #[test]
+    fn classify_thresholds(){
+        let cases: Vec<Case> = vec![
+            //DOT_LENGTH
+            ("短于点的长度", vec![(100, 500), (179, 500)], StrokeKind::Dot),
+            ("长于点的长度", vec![(100, 500), (181, 500)], StrokeKind::Horizontal),
+            //TURN_ANGLE: 两段之间转过55度和65度
+            ("小于转折角度", vec![(100, 500), (500, 500), (729, 828)], StrokeKind::Pressing),
+            ("大于转折角度", vec![(100, 500), (500, 500), (669, 863)], StrokeKind::Turning),
+            //CURVE_RATIO: 首尾距离和长度之比约为0.88和0.80
+            ("接近直线", bend(35.0), StrokeKind::Pressing),
+            ("弯曲", bend(45.0), StrokeKind::Curve),
+            //LOOP_RATIO: 300度的弧首尾距离约为长度的0.19, 290度约为0.23
+            ("首尾接近", arc(300.0, 24), StrokeKind::Loop),
+            ("首尾不够接近", arc(290.0, 24), StrokeKind::Curve),
+        ];
+        for (name, stroke, kind) in cases{
+            assert_eq!(classify(&stroke), kind, "{}", name);
+        }
+    }
+
+    #[test]
+    fn rules_forbid_kinds_per_script(){
+        let strokes = vec![arc(345.0, 24), vec![(900, 500), (100, 500)], vec![(100, 500), (900, 500)]];
+        //(文字, 有问题的笔画)
+        let cases = [
+            (Script::Han, vec!["第1笔是圈", "第2笔是反向的笔画"]),
+            (Script::Hangul, vec!["第2笔是反向的笔画"]),
+            (Script::Kana, vec![]),
+            (Script::Latin, vec![]),
+        ];
+        for (script, expected) in cases.iter(){
+            let problems = rules(*script, &strokes);
+            assert_eq!(problems.len(), expected.len(), "{:?}", script);
+            for (problem, expected) in problems.iter().zip(expected.iter()){
+                assert!(problem.starts_with(expected), "{:?}: {}", script, problem);
+            }
+        }
+    }
+
+    #[test]
+    fn rules_check_max_strokes(){
+        for script in Script::ALL.iter(){
+            let mut strokes = vec![vec![(100, 500), (900, 500)]; script.max_strokes()];
+            assert!(rules(*script, &strokes).is_empty(), "{:?}", script);
+            strokes.push(vec![(100, 500), (900, 500)]);
+            assert_eq!(rules(*script, &strokes).len(), 1, "{:?}", script);
+        }
+    }
+
+    #[test]
+    fn majority_breaks_ties_in_order(){
+        let cases: [(&str, Script); 7] = [
+            ("", Script::Han),
+            ("123。", Script::Han),
+            ("一あ", Script::Han),
+            ("aあ", Script::Kana),
+            ("a가", Script::Hangul),
+            ("一あい", Script::Kana),
+            ("12ab一", Script::Latin),
+        ];
+        for (chars, expected) in cases.iter(){
+            let chars: Vec<char> = chars.chars().collect();
+            assert_eq!(majority(&chars), *expected, "{:?}", chars);
+        }
+        //指定了文字时整个数据都按这种文字处理
+        let data = DataScript::detect(&['一', 'あ', 'い'], None);
+        assert_eq!((data.main(), data.of('一'), data.of('1')), (Script::Kana, Script::Han, Script::Kana));
+        let data = DataScript::detect(&['一'], Some(Script::Latin));
+        assert_eq!((data.main(), data.of('一')), (Script::Latin, Script::Latin));
+    }
+}
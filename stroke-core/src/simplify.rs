@@ -0,0 +1,96 @@
+//点到线段ab的距离
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64{
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0{
+        0.0
+    }else{
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - x).powi(2) + (p.1 - y).powi(2)).sqrt()
+}
+
+//Ramer-Douglas-Peucker折线简化, 返回保留的点的下标(包含首尾两点)
+pub fn simplify(points: &[(f64, f64)], tolerance: f64) -> Vec<usize>{
+    if points.len() < 3{
+        return (0..points.len()).collect();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop(){
+        let mut max = (0.0, start);
+        for i in start + 1..end{
+            let distance = segment_distance(points[i], points[start], points[end]);
+            if distance > max.0{
+                max = (distance, i);
+            }
+        }
+        if max.0 > tolerance{
+            keep[max.1] = true;
+            stack.push((start, max.1));
+            stack.push((max.1, end));
+        }
+    }
+    (0..points.len()).filter(|&i| keep[i]).collect()
+}
+
+//简化到不超过max_points个点, 点太多时逐步增大容差(容差为0或负数时从最小的正数开始增大, 否则不会结束)
+pub fn simplify_to(points: &[(f64, f64)], tolerance: f64, max_points: usize) -> Vec<usize>{
+    let mut tolerance = tolerance.max(f64::EPSILON);
+    loop{
+        let keep = simplify(points, tolerance);
+        if keep.len() <= max_points.max(2){
+            return keep;
+        }
+        tolerance *= 1.5;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn straight_line_keeps_end_points(){
+        let points: Vec<(f64, f64)> = (0..=10).map(|i| (i as f64 * 10.0, 0.5 * (i % 2) as f64)).collect();
+        assert_eq!(simplify(&points, 1.0), vec![0, 10]);
+    }
+
+    #[test]
+    fn corner_is_kept(){
+        let points = [(0.0, 0.0), (50.0, 1.0), (100.0, 0.0), (100.0, 50.0), (100.0, 100.0)];
+        assert_eq!(simplify(&points, 5.0), vec![0, 2, 4]);
+        //容差为0时保留所有偏离直线的点
+        assert_eq!(simplify(&points, 0.0), vec![0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn short_paths_are_unchanged(){
+        assert_eq!(simplify(&[], 1.0), Vec::<usize>::new());
+        assert_eq!(simplify(&[(1.0, 1.0), (2.0, 2.0)], 1.0), vec![0, 1]);
+    }
+
+    #[test]
+    fn simplify_to_limits_points(){
+        //锯齿线, 容差很小时会保留所有点
+        let points: Vec<(f64, f64)> = (0..100).map(|i| (i as f64 * 10.0, if i % 2 == 0 { 0.0 } else { 20.0 })).collect();
+        let keep = simplify_to(&points, 1.0, 8);
+        assert!(keep.len() <= 8);
+        assert_eq!((keep[0], keep[keep.len() - 1]), (0, 99));
+    }
+
+    #[test]
+    fn simplify_to_with_zero_tolerance_terminates(){
+        let points: Vec<(f64, f64)> = (0..100).map(|i| (i as f64 * 10.0, if i % 2 == 0 { 0.0 } else { 20.0 })).collect();
+        for tolerance in [0.0, -1.0]{
+            let keep = simplify_to(&points, tolerance, 8);
+            assert!(keep.len() <= 8);
+            assert_eq!((keep[0], keep[keep.len() - 1]), (0, 99));
+        }
+        //不需要简化时保留所有点
+        assert_eq!(simplify_to(&points[..5], 0.0, 8), vec![0, 1, 2, 3, 4]);
+    }
+}
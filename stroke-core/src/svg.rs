@@ -0,0 +1,41 @@
+use std::fmt::Write;
+use crate::data::{Strokes, MAX_COORD};
+
+//笔画的线宽(字形坐标)
+const LINE_WIDTH: u32 = 30;
+
+//一个字的SVG路径, 每一笔是一个"M x y L x y ..."
+fn path(strokes:&Strokes) -> String{
+    let mut path = String::new();
+    for stroke in strokes{
+        for (i, (x, y)) in stroke.iter().enumerate(){
+            write!(path, "{}{} {} ", if i == 0 { "M" } else { "L" }, x, y).unwrap();
+        }
+    }
+    path.trim_end().to_string()
+}
+
+//把一个字绘制为size×size的SVG
+pub fn char_svg(strokes:&Strokes, size: u32) -> String{
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {max} {max}\">\n\
+        <path d=\"{path}\" fill=\"none\" stroke=\"#000\" stroke-width=\"{width}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n</svg>\n",
+        size = size, max = MAX_COORD, path = path(strokes), width = LINE_WIDTH)
+}
+
+//把多个字按照columns列排列绘制为一个SVG, 每个字size×size
+pub fn grid_svg(chars:&[(char, &Strokes)], size: u32, columns: usize) -> String{
+    let columns = columns.max(1);
+    let rows = chars.len().div_ceil(columns);
+    let (width, height) = (size * columns.min(chars.len()).max(1) as u32, size * rows.max(1) as u32);
+    let scale = size as f64 / MAX_COORD as f64;
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n", width, height);
+    for (i, (ch, strokes)) in chars.iter().enumerate(){
+        let (x, y) = ((i % columns) as u32 * size, (i / columns) as u32 * size);
+        writeln!(svg, "<g transform=\"translate({} {}) scale({})\"><title>{}</title>\
+            <rect width=\"{max}\" height=\"{max}\" fill=\"none\" stroke=\"#ccc\" stroke-width=\"{}\"/>\
+            <path d=\"{}\" fill=\"none\" stroke=\"#000\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/></g>",
+            x, y, scale, ch, 1.0 / scale, path(strokes), LINE_WIDTH, max = MAX_COORD).unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use bincode::{deserialize, serialize};
+
+//附加在数据(审核信息之后)和补丁后面的异体字关系的标记
+const MAGIC: &[u8] = b"VARIANT1";
+
+//异体字的关系
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind{
+    //对应的简体字
+    Simplified,
+    //对应的繁体字
+    Traditional,
+    //意义相同的异体字
+    Semantic,
+    //字形稍有不同的同一个字
+    Shape,
+}
+
+impl Kind{
+    pub fn name(&self) -> &'static str{
+        match self{
+            Kind::Simplified => "简体",
+            Kind::Traditional => "繁体",
+            Kind::Semantic => "异体",
+            Kind::Shape => "字形变体",
+        }
+    }
+
+    //反方向的关系
+    fn reverse(&self) -> Kind{
+        match self{
+            Kind::Simplified => Kind::Traditional,
+            Kind::Traditional => Kind::Simplified,
+            kind => *kind,
+        }
+    }
+}
+
+//异体字表: Unihan数据库中的关系, 或者数据中保存的关系(创建异体字时记录)
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Variants{
+    links: BTreeMap<char, Vec<(char, Kind)>>,
+}
+
+//"U+6C34"或者带来源的"U+6C34<kMatthews"
+fn parse_code_point(text:&str) -> Option<char>{
+    let text = text.split('<').next()?;
+    u32::from_str_radix(text.strip_prefix("U+")?, 16).ok().and_then(char::from_u32)
+}
+
+impl Variants{
+    pub fn is_empty(&self) -> bool{
+        self.links.is_empty()
+    }
+
+    //添加ch到variant的关系, 同时添加反方向的关系
+    pub fn link(&mut self, ch: char, variant: char, kind: Kind){
+        if ch == variant{
+            return;
+        }
+        for (from, to, kind) in [(ch, variant, kind), (variant, ch, kind.reverse())]{
+            let links = self.links.entry(from).or_default();
+            if !links.iter().any(|(c, _)| *c == to){
+                links.push((to, kind));
+            }
+        }
+    }
+
+    //合并另一个异体字表, 已经有的关系不变
+    pub fn merge(&mut self, other:&Variants){
+        for (ch, links) in &other.links{
+            for (variant, kind) in links{
+                self.link(*ch, *variant, *kind);
+            }
+        }
+    }
+
+    //和base相比新增的关系(补丁中只记录新增的关系)
+    pub fn added(&self, base:&Variants) -> Variants{
+        let mut added = Variants::default();
+        for (ch, links) in &self.links{
+            for (variant, kind) in links{
+                if !base.links.get(ch).is_some_and(|links| links.iter().any(|(c, _)| c == variant)){
+                    added.link(*ch, *variant, *kind);
+                }
+            }
+        }
+        added
+    }
+
+    //Unihan数据库的Unihan_Variants.txt
+    pub fn add_unihan(&mut self, text:&str){
+        for line in text.lines(){
+            if line.starts_with('#'){
+                continue;
+            }
+            let mut fields = line.splitn(3, '\t');
+            let (ch, field, value) = match (fields.next().and_then(parse_code_point), fields.next(), fields.next()){
+                (Some(ch), Some(field), Some(value)) => (ch, field, value),
+                _ => continue
+            };
+            let kind = match field{
+                "kSimplifiedVariant" => Kind::Simplified,
+                "kTraditionalVariant" => Kind::Traditional,
+                "kSemanticVariant" => Kind::Semantic,
+                "kZVariant" => Kind::Shape,
+                _ => continue
+            };
+            for variant in value.split_whitespace().filter_map(parse_code_point){
+                self.link(ch, variant, kind);
+            }
+        }
+    }
+
+    //ch的所有异体字, 简繁体在前面
+    pub fn get(&self, ch: char) -> Vec<(char, Kind)>{
+        let mut links = self.links.get(&ch).cloned().unwrap_or_default();
+        links.sort_by_key(|(_, kind)| matches!(kind, Kind::Semantic | Kind::Shape));
+        links
+    }
+}
+
+//在数据或补丁后面附加异体字关系, 没有时不附加
+pub fn append_variants(data:&mut Vec<u8>, variants:&Variants){
+    if variants.is_empty(){
+        return;
+    }
+    data.extend_from_slice(MAGIC);
+    data.extend(serialize(variants).unwrap());
+}
+
+//读取offset(前面数据的长度)之后附加的异体字关系, 没有时返回空
+pub fn read_variants(data:&[u8], offset: usize) -> Result<Variants, String>{
+    match data.get(offset..){
+        Some(rest) if rest.starts_with(MAGIC) => deserialize(&rest[MAGIC.len()..]).map_err(|err| format!("异体字关系格式错误:{}", err)),
+        _ => Ok(Variants::default())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const UNIHAN: &str = "# Unihan_Variants.txt
+U+8AAA\tkSimplifiedVariant\tU+8BF4
+U+8AAA\tkSemanticVariant\tU+6085<kMatthews
+U+8AAA\tkZVariant\tU+8AAC
+U+8BF4\tkTraditionalVariant\tU+8AAA
+U+8AAA\tkDefinition\tspeak
+bad line
+";
+
+    #[test]
+    fn reads_unihan(){
+        let mut variants = Variants::default();
+        assert!(variants.is_empty());
+        variants.add_unihan(UNIHAN);
+        //简繁体在前面, 重复的关系只记录一次
+        assert_eq!(variants.get('說'), vec![('说', Kind::Simplified), ('悅', Kind::Semantic), ('説', Kind::Shape)]);
+        assert_eq!(variants.get('说'), vec![('說', Kind::Traditional)]);
+        //反方向的关系
+        assert_eq!(variants.get('悅'), vec![('說', Kind::Semantic)]);
+        assert!(variants.get('一').is_empty());
+    }
+
+    #[test]
+    fn link_ignores_self(){
+        let mut variants = Variants::default();
+        variants.link('一', '一', Kind::Shape);
+        assert!(variants.is_empty());
+    }
+
+    #[test]
+    fn merges_and_finds_added_links(){
+        let mut base = Variants::default();
+        base.link('說', '说', Kind::Simplified);
+        let mut current = base.clone();
+        current.link('說', '説', Kind::Shape);
+        let added = current.added(&base);
+        assert_eq!(added.get('說'), vec![('説', Kind::Shape)]);
+        assert_eq!(added.get('説'), vec![('說', Kind::Shape)]);
+        assert!(added.get('说').is_empty());
+        base.merge(&added);
+        assert_eq!(base, current);
+    }
+
+    #[test]
+    fn variants_round_trip(){
+        let mut variants = Variants::default();
+        variants.link('說', '说', Kind::Simplified);
+        let mut data = b"list".to_vec();
+        append_variants(&mut data, &variants);
+        assert_eq!(read_variants(&data, 4).unwrap(), variants);
+        //没有附加时返回空
+        assert!(read_variants(b"list", 4).unwrap().is_empty());
+        let mut data = b"list".to_vec();
+        append_variants(&mut data, &Variants::default());
+        assert_eq!(data, b"list");
+    }
+}
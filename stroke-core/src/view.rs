@@ -0,0 +1,177 @@
+//字形坐标系的大小(笔画数据中的点都在0~GLYPH_SIZE之间)
+pub const GLYPH_SIZE: f64 = 1000.0;
+
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 20.0;
+
+//画布视图: 字形坐标 -> 画布CSS像素坐标(绘制时再乘以devicePixelRatio)
+//canvas_x = glyph_x * scale + offset_x
+#[derive(Clone, Copy, Debug)]
+pub struct View{
+    pub scale: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl Default for View{
+    fn default() -> View{
+        View{
+            scale: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+}
+
+impl View{
+    //画布坐标转换为字形坐标
+    pub fn canvas_to_glyph(&self, x: f64, y: f64) -> (f64, f64){
+        ((x - self.offset_x) / self.scale, (y - self.offset_y) / self.scale)
+    }
+
+    //以画布上的(x, y)为中心缩放, 缩放后该点对应的字形坐标不变
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64){
+        let (gx, gy) = self.canvas_to_glyph(x, y);
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.offset_x = x - gx * self.scale;
+        self.offset_y = y - gy * self.scale;
+    }
+
+    //画布大小改变: 保持中心点对应的字形坐标不变, 按照短边的比例缩放
+    pub fn resize(&mut self, old_size: (f64, f64), new_size: (f64, f64)){
+        let (old_side, new_side) = (old_size.0.min(old_size.1), new_size.0.min(new_size.1));
+        if old_side <= 0.0 || new_side <= 0.0{
+            self.fit((0.0, 0.0, GLYPH_SIZE, GLYPH_SIZE), new_size.0, new_size.1, 0.0);
+            return;
+        }
+        let (cx, cy) = self.canvas_to_glyph(old_size.0 / 2.0, old_size.1 / 2.0);
+        self.scale *= new_side / old_side;
+        self.offset_x = new_size.0 / 2.0 - cx * self.scale;
+        self.offset_y = new_size.1 / 2.0 - cy * self.scale;
+    }
+
+    //平移(画布CSS像素)
+    pub fn pan(&mut self, dx: f64, dy: f64){
+        self.offset_x += dx;
+        self.offset_y += dy;
+    }
+
+    //将字形区域(left, top, right, bottom)缩放到画布中央, margin为四周留白(字形坐标)
+    pub fn fit(&mut self, rect: (f64, f64, f64, f64), width: f64, height: f64, margin: f64){
+        let (left, top, right, bottom) = (rect.0 - margin, rect.1 - margin, rect.2 + margin, rect.3 + margin);
+        let (w, h) = ((right - left).max(1.0), (bottom - top).max(1.0));
+        self.scale = (width / w).min(height / h).clamp(MIN_SCALE, MAX_SCALE);
+        self.offset_x = (width - (left + right) * self.scale) / 2.0;
+        self.offset_y = (height - (top + bottom) * self.scale) / 2.0;
+    }
+}
+
+//笔画所有点的包围盒
+pub fn bounding_box(strokes: &[Vec<(u16, u16)>]) -> Option<(f64, f64, f64, f64)>{
+    let mut points = strokes.iter().flatten();
+    let first = points.next()?;
+    let mut rect = (first.0 as f64, first.1 as f64, first.0 as f64, first.1 as f64);
+    for point in points{
+        let (x, y) = (point.0 as f64, point.1 as f64);
+        rect.0 = rect.0.min(x);
+        rect.1 = rect.1.min(y);
+        rect.2 = rect.2.max(x);
+        rect.3 = rect.3.max(y);
+    }
+    Some(rect)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    //字形坐标转换为画布坐标(绘制时由画布的变换完成)
+    fn glyph_to_canvas(view:&View, (x, y): (f64, f64)) -> (f64, f64){
+        (x * view.scale + view.offset_x, y * view.scale + view.offset_y)
+    }
+
+    fn assert_near(a: (f64, f64), b: (f64, f64)){
+        assert!((a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    fn views() -> Vec<View>{
+        vec![
+            View::default(),
+            View{ scale: 0.4, offset_x: 120.0, offset_y: -35.5 },
+            View{ scale: 7.25, offset_x: -3000.0, offset_y: 48.0 },
+        ]
+    }
+
+    #[test]
+    fn canvas_glyph_round_trip(){
+        for view in views(){
+            for point in [(0.0, 0.0), (250.0, 125.5), (-40.0, 900.0)]{
+                assert_near(glyph_to_canvas(&view, view.canvas_to_glyph(point.0, point.1)), point);
+            }
+        }
+    }
+
+    #[test]
+    fn zoom_keeps_point_under_cursor(){
+        for mut view in views(){
+            let cursor = (173.0, 248.0);
+            let glyph = view.canvas_to_glyph(cursor.0, cursor.1);
+            for factor in [1.25, 0.8, 3.0, 1000.0, 0.0001]{
+                view.zoom_at(cursor.0, cursor.1, factor);
+                assert!(view.scale >= MIN_SCALE && view.scale <= MAX_SCALE);
+                assert_near(view.canvas_to_glyph(cursor.0, cursor.1), glyph);
+            }
+        }
+        //放大再缩小回到原来的视图
+        let mut view = View{ scale: 0.4, offset_x: 120.0, offset_y: -35.5 };
+        view.zoom_at(50.0, 60.0, 2.0);
+        view.zoom_at(50.0, 60.0, 0.5);
+        assert_near((view.scale, view.offset_x), (0.4, 120.0));
+        assert_near((view.offset_y, 0.0), (-35.5, 0.0));
+    }
+
+    #[test]
+    fn pan_moves_canvas_points(){
+        let mut view = View{ scale: 0.4, offset_x: 120.0, offset_y: -35.5 };
+        let before = glyph_to_canvas(&view, (500.0, 500.0));
+        view.pan(30.0, -12.0);
+        assert_near(glyph_to_canvas(&view, (500.0, 500.0)), (before.0 + 30.0, before.1 - 12.0));
+        assert_eq!(view.scale, 0.4);
+    }
+
+    #[test]
+    fn fit_centers_rect(){
+        let mut view = View::default();
+        //字形区域宽200高100, 画布400x400: 按宽度缩放, 垂直居中
+        view.fit((100.0, 300.0, 300.0, 400.0), 400.0, 400.0, 0.0);
+        assert_eq!(view.scale, 2.0);
+        assert_near(glyph_to_canvas(&view, (100.0, 300.0)), (0.0, 100.0));
+        assert_near(glyph_to_canvas(&view, (300.0, 400.0)), (400.0, 300.0));
+        //留白按字形坐标计算
+        view.fit((0.0, 0.0, GLYPH_SIZE, GLYPH_SIZE), 600.0, 300.0, 250.0);
+        assert_eq!(view.scale, 0.2);
+        assert_near(glyph_to_canvas(&view, (GLYPH_SIZE / 2.0, GLYPH_SIZE / 2.0)), (300.0, 150.0));
+        //一个点不会除以0, 缩放在范围之内
+        view.fit((500.0, 500.0, 500.0, 500.0), 400.0, 400.0, 0.0);
+        assert_eq!(view.scale, MAX_SCALE);
+        assert_near(glyph_to_canvas(&view, (500.0, 500.0)), (200.0, 200.0));
+    }
+
+    #[test]
+    fn bounding_box_covers_all_points(){
+        assert_eq!(bounding_box(&[]), None);
+        assert_eq!(bounding_box(&[vec![], vec![]]), None);
+        let strokes = vec![vec![(300, 200), (700, 250)], vec![], vec![(500, 100), (450, 900)]];
+        assert_eq!(bounding_box(&strokes), Some((300.0, 100.0, 700.0, 900.0)));
+        assert_eq!(bounding_box(&[vec![(5, 6)]]), Some((5.0, 6.0, 5.0, 6.0)));
+    }
+
+    #[test]
+    fn resize_keeps_center(){
+        let mut view = View{ scale: 0.4, offset_x: 120.0, offset_y: -35.5 };
+        let center = view.canvas_to_glyph(200.0, 150.0);
+        view.resize((400.0, 300.0), (800.0, 600.0));
+        assert_near((view.scale, 0.0), (0.8, 0.0));
+        assert_near(view.canvas_to_glyph(400.0, 300.0), center);
+    }
+}
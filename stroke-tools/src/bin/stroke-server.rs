@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use stroke_core::data::{list_size, problems, read_any, write_list, StrokeList, Strokes};
+use stroke_core::patch::read_patch;
+use stroke_core::review::{append_reviews, apply_changes, read_reviews, reviews_size, Reviews};
+use stroke_core::variant::{append_variants, read_variants, Variants};
+
+const USAGE: &str = "用法:
+    stroke-server [--root html] [--data html/STROKES.data] [--host 127.0.0.1] [--port 3000] [--token 令牌] [--backups 备份目录]
+
+提供root目录中的页面, 并接受带有\"Authorization: Bearer 令牌\"的请求保存数据:
+    GET   /api/status  服务器信息(数据文件名)
+    PUT   /api/data    替换整个数据(STROKES.data格式)
+    PATCH /api/data    应用补丁(STROKES.patch格式, 附带修改过的审核信息和新增的异体字关系), 补丁中原来的笔画和服务器上的不同时返回409
+没有指定令牌时使用环境变量STROKE_SERVER_TOKEN, 都没有时从/dev/urandom随机生成(Windows上必须指定)。每次保存前把原来的数据复制到备份目录。";
+
+//请求体的最大长度
+const MAX_BODY: usize = 64 * 1024 * 1024;
+//读写超时, 请求是逐个处理的, 慢的连接不能一直占用服务器
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Config{
+    root: PathBuf,
+    data: PathBuf,
+    backups: PathBuf,
+    token: String,
+}
+
+struct Request{
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response{
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response{
+    fn text(status: &'static str, text:&str) -> Response{
+        Response{ status, content_type: "text/plain; charset=utf-8", body: text.as_bytes().to_vec() }
+    }
+}
+
+//取出"--name value"形式的参数
+fn take_option(args:&mut Vec<String>, name:&str) -> Result<Option<String>, String>{
+    match args.iter().position(|arg| arg == name){
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("{}缺少参数", name)),
+        None => Ok(None)
+    }
+}
+
+//比较令牌, 用时和不同的位置无关
+fn same_token(a:&[u8], b:&[u8]) -> bool{
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+//解码路径中的%XX(例如中文文件名)
+fn percent_decode(path:&str) -> Result<String, String>{
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len(){
+        if bytes[i] == b'%'{
+            let hex = bytes.get(i + 1..i + 3).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok());
+            match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()){
+                Some(byte) => decoded.push(byte),
+                None => return Err("路径格式错误".to_string())
+            }
+            i += 3;
+        }else{
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "路径格式错误".to_string())
+}
+
+//从系统的随机数生成器生成令牌, 没有/dev/urandom的系统(Windows)需要指定令牌
+fn random_token() -> Result<String, String>{
+    let mut bytes = [0u8; 16];
+    fs::File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes))
+        .map_err(|err| format!("无法生成随机令牌({}), 请用--token或者环境变量STROKE_SERVER_TOKEN指定令牌", err))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn read_request(stream:&mut TcpStream) -> Result<Request, String>{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| err.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()){
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Err("请求格式错误".to_string())
+    };
+    let (mut length, mut authorization) = (0, None);
+    loop{
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|err| err.to_string())? == 0 || line.trim().is_empty(){
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':'){
+            match name.trim().to_lowercase().as_str(){
+                "content-length" => length = value.trim().parse().map_err(|_| "Content-Length格式错误".to_string())?,
+                "authorization" => authorization = Some(value.trim().to_string()),
+                _ => ()
+            }
+        }
+    }
+    if length > MAX_BODY{
+        return Err("请求体太大".to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|err| err.to_string())?;
+    //去掉查询参数之后解码
+    let path = percent_decode(path.split(['?', '#']).next().unwrap_or("/"))?;
+    Ok(Request{ method, path, authorization, body })
+}
+
+fn write_response(stream:&mut TcpStream, response:&Response) -> std::io::Result<()>{
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        response.status, response.content_type, response.body.len())?;
+    stream.write_all(&response.body)
+}
+
+fn content_type(path:&Path) -> &'static str{
+    match path.extension().and_then(|ext| ext.to_str()).unwrap_or(""){
+        "html" => "text/html; charset=utf-8",
+        "js" => "application/javascript",
+        "wasm" => "application/wasm",
+        "json" => "application/json",
+        "png" => "image/png",
+        "ttf" => "font/ttf",
+        _ => "application/octet-stream"
+    }
+}
+
+//提供root目录中的文件, 数据文件的名字对应--data指定的文件
+fn serve_file(config:&Config, path:&str) -> Response{
+    let relative = Path::new(path.trim_start_matches('/'));
+    if relative.components().any(|component| !matches!(component, Component::Normal(_))){
+        return Response::text("400 Bad Request", "路径错误");
+    }
+    let file = if relative.as_os_str().is_empty(){
+        config.root.join("index.html")
+    }else if relative.file_name() == config.data.file_name() && relative.parent() == Some(Path::new("")){
+        config.data.clone()
+    }else{
+        config.root.join(relative)
+    };
+    match fs::read(&file){
+        Ok(body) => Response{ status: "200 OK", content_type: content_type(&file), body },
+        Err(_) => Response::text("404 Not Found", "文件不存在")
+    }
+}
+
+//和原来的数据相比新增或修改的字的问题, 只作为警告(新添加的字只有一个点, 原来数据中的问题也不应该妨碍保存)
+fn warnings(old:&[(char, Strokes)], list:&[(char, Strokes)]) -> Vec<(char, String)>{
+    let old: HashMap<char, &Strokes> = old.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    list.iter().filter(|(ch, strokes)| old.get(ch) != Some(&strokes))
+        .flat_map(|(ch, strokes)| problems(strokes).into_iter().map(move |problem| (*ch, problem)))
+        .collect()
+}
+
+//备份原来的数据之后写入新数据(先写临时文件再改名, 避免写入一半)
+fn save(config:&Config, list:&StrokeList, reviews:&Reviews, variants:&Variants, warnings:&[(char, String)]) -> Result<String, String>{
+    fs::create_dir_all(&config.backups).map_err(|err| err.to_string())?;
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+    let stem = config.data.file_stem().and_then(|stem| stem.to_str()).unwrap_or("STROKES");
+    let backup = config.backups.join(format!("{}-{}.data", stem, time));
+    if config.data.exists(){
+        fs::copy(&config.data, &backup).map_err(|err| err.to_string())?;
+    }
+    let temp = config.data.with_extension("tmp");
+    let mut data = write_list(list);
+    append_reviews(&mut data, reviews);
+    append_variants(&mut data, variants);
+    fs::write(&temp, data).map_err(|err| err.to_string())?;
+    fs::rename(&temp, &config.data).map_err(|err| err.to_string())?;
+    let mut message = format!("已保存{}个字, 备份:{}", list.len(), backup.display());
+    if let Some((ch, problem)) = warnings.first(){
+        message += &format!("\n修改的字有{}个问题, 例如{}: {}", warnings.len(), ch, problem);
+    }
+    Ok(message)
+}
+
+//数据和后面附加的审核信息、异体字关系
+fn parse(data:&[u8]) -> Result<(StrokeList, Reviews, Variants), String>{
+    let (list, _) = read_any(data)?;
+    let reviews = read_reviews(data, list_size(&list))?;
+    let variants = read_variants(data, list_size(&list) + reviews_size(&reviews))?;
+    Ok((list, reviews, variants))
+}
+
+fn load(config:&Config) -> Result<(StrokeList, Reviews, Variants), String>{
+    parse(&fs::read(&config.data).map_err(|err| err.to_string())?)
+}
+
+fn handle(config:&Config, request:&Request) -> Response{
+    match (request.method.as_str(), request.path.as_str()){
+        ("GET", "/api/status") => {
+            let name = config.data.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            Response{
+                status: "200 OK",
+                content_type: "application/json",
+                body: format!("{{\"data\":\"{}\"}}", name.replace('\\', "\\\\").replace('"', "\\\"")).into_bytes()
+            }
+        }
+        (method, "/api/data") if method == "PUT" || method == "PATCH" => {
+            let expected = format!("Bearer {}", config.token);
+            if !same_token(request.authorization.as_deref().unwrap_or("").as_bytes(), expected.as_bytes()){
+                return Response::text("401 Unauthorized", "令牌错误");
+            }
+            //服务器上原来的数据, PUT替换整个数据, 原来的数据不存在或者损坏时也可以写入
+            let (current, current_reviews, current_variants) = match load(config){
+                Ok(data) => data,
+                Err(_) if method == "PUT" => (vec![], Reviews::new(), Variants::default()),
+                Err(err) => return Response::text("500 Internal Server Error", &err)
+            };
+            let (list, reviews, variants) = if method == "PUT"{
+                match parse(&request.body){
+                    Ok(data) => data,
+                    Err(err) => return Response::text("400 Bad Request", &err)
+                }
+            }else{
+                let (patch, changed, added) = match read_patch(&request.body){
+                    Ok(patch) => patch,
+                    Err(err) => return Response::text("400 Bad Request", &err)
+                };
+                let mut reviews = current_reviews;
+                //补丁中原来的笔画和服务器上的数据不一致(其他人已经修改过), 成功时才使用修改后的数据
+                let mut list = current.clone();
+                if let Err(err) = patch.apply(&mut list){
+                    return Response::text("409 Conflict", &err);
+                }
+                //审核信息按字覆盖, 删除或者改名的字去掉原来的审核信息
+                apply_changes(&mut reviews, &changed);
+                //异体字关系只会新增
+                let mut variants = current_variants;
+                variants.merge(&added);
+                (list, reviews, variants)
+            };
+            match save(config, &list, &reviews, &variants, &warnings(&current, &list)){
+                Ok(message) => {
+                    println!("{} {}", method, message);
+                    Response::text("200 OK", &message)
+                }
+                Err(err) => Response::text("500 Internal Server Error", &err)
+            }
+        }
+        ("GET", path) => serve_file(config, path),
+        _ => Response::text("405 Method Not Allowed", "不支持的请求")
+    }
+}
+
+fn run(mut args: Vec<String>) -> Result<(), String>{
+    if args.iter().any(|arg| arg == "--help" || arg == "-h"){
+        return Err(USAGE.to_string());
+    }
+    let root = PathBuf::from(take_option(&mut args, "--root")?.unwrap_or_else(|| "html".to_string()));
+    let data = take_option(&mut args, "--data")?.map(PathBuf::from).unwrap_or_else(|| root.join("STROKES.data"));
+    let backups = take_option(&mut args, "--backups")?.map(PathBuf::from)
+        .unwrap_or_else(|| data.parent().unwrap_or(Path::new(".")).join("backups"));
+    let host = take_option(&mut args, "--host")?.unwrap_or_else(|| "127.0.0.1".to_string());
+    let port = take_option(&mut args, "--port")?.unwrap_or_else(|| "3000".to_string());
+    let token = take_option(&mut args, "--token")?
+        .or_else(|| std::env::var("STROKE_SERVER_TOKEN").ok())
+        .map(Ok).unwrap_or_else(random_token)?;
+    if !args.is_empty(){
+        return Err(USAGE.to_string());
+    }
+    let config = Config{ root, data, backups, token };
+    let listener = TcpListener::bind(format!("{}:{}", host, port)).map_err(|err| format!("{}:{}: {}", host, port, err))?;
+    println!("http://{}:{}/", host, port);
+    println!("数据文件: {}", config.data.display());
+    println!("令牌: {}", config.token);
+    //逐个处理请求, 保存不会同时进行
+    for stream in listener.incoming(){
+        let mut stream = match stream{
+            Ok(stream) => stream,
+            Err(_) => continue
+        };
+        if let Err(err) = stream.set_read_timeout(Some(TIMEOUT)).and_then(|_| stream.set_write_timeout(Some(TIMEOUT))){
+            eprintln!("{}", err);
+            continue;
+        }
+        let response = match read_request(&mut stream){
+            Ok(request) => handle(&config, &request),
+            Err(err) => Response::text("400 Bad Request", &err)
+        };
+        if let Err(err) = write_response(&mut stream, &response){
+            eprintln!("{}", err);
+        }
+    }
+    Ok(())
+}
+
+fn main(){
+    if let Err(err) = run(std::env::args().skip(1).collect()){
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process;
+use stroke_core::data::{list_size, read_any, validate, write_list, write_map, StrokeList, Strokes};
+use stroke_core::review::{append_reviews, merge_reviews, read_reviews, reviews_size, Reviews};
+use stroke_core::coverage::{format_list, Coverage};
+use stroke_core::diff::diff;
+use stroke_core::merge::merge;
+use stroke_core::duplicate::{duplicates, NEAR_THRESHOLD};
+use stroke_core::recognize::{Candidate, Recognizer};
+use stroke_core::script::{rules, DataScript, Script};
+use stroke_core::svg::{char_svg, grid_svg};
+use stroke_core::variant::{append_variants, read_variants, Kind, Variants};
+
+//recognize默认列出的候选数
+const DEFAULT_TOP: usize = 5;
+
+//默认的点移动容差(字形坐标)
+const DEFAULT_TOLERANCE: f64 = 5.0;
+
+//SVG中每个字的大小(像素)
+const SVG_SIZE: u32 = 200;
+//render每行的字数
+const RENDER_COLUMNS: usize = 10;
+
+const USAGE: &str = "用法:
+    stroke-tool validate <STROKES.data> [--script auto|han|kana|hangul|latin]
+    stroke-tool convert <输入文件> <输出文件> [--format vec|map|json|svg]
+    stroke-tool stats <STROKES.data>
+    stroke-tool diff <旧STROKES.data> <新STROKES.data> [--tolerance 容差]
+    stroke-tool merge <原始STROKES.data> <我们的STROKES.data> <他们的STROKES.data> -o <输出文件>
+    stroke-tool render <STROKES.data> <字...> -o <输出.svg> [--size 像素]
+    stroke-tool query <STROKES.data> [字...] [--strokes 笔画数]
+    stroke-tool coverage <STROKES.data> <文本文件> [--missing 缺少的字.txt] [--script 文字]
+    stroke-tool variants <STROKES.data> <Unihan_Variants.txt>
+    stroke-tool recognize <STROKES.data> <字...|笔画.json> [--top 候选数]
+    stroke-tool duplicates <STROKES.data> [--threshold 距离]
+
+输入文件可以是STROKES.data(Vec格式)、gb2312.data(HashMap格式)或者.json文件。
+--script指定整个数据使用的文字, 默认自动(按每个字的Unicode范围判断, 数字和标点按数据中最多的文字)。
+convert的输出格式默认根据扩展名判断(.json为json, .svg为svg, 其他为vec), svg格式会把每个字输出到目录中的<字>.svg。";
+
+fn is_json(path:&str) -> bool{
+    path.to_lowercase().ends_with(".json")
+}
+
+fn load(path:&str) -> Result<StrokeList, String>{
+    let data = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    if is_json(path){
+        serde_json::from_slice(&data).map_err(|err| format!("{}: JSON格式错误:{}", path, err))
+    }else{
+        read_any(&data).map(|(list, _)| list).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+//读取附加在数据后面的审核信息和异体字关系
+fn load_extras(path:&str, list:&[(char, Strokes)]) -> Result<(Reviews, Variants), String>{
+    if is_json(path){
+        return Ok((Reviews::new(), Variants::default()));
+    }
+    let data = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    let reviews = read_reviews(&data, list_size(list)).map_err(|err| format!("{}: {}", path, err))?;
+    let variants = read_variants(&data, list_size(list) + reviews_size(&reviews)).map_err(|err| format!("{}: {}", path, err))?;
+    Ok((reviews, variants))
+}
+
+fn write(path:&str, data:&[u8]) -> Result<(), String>{
+    fs::write(path, data).map_err(|err| format!("{}: {}", path, err))
+}
+
+//取出"--name value"形式的参数
+fn take_option(args:&mut Vec<String>, names:&[&str]) -> Result<Option<String>, String>{
+    match args.iter().position(|arg| names.contains(&arg.as_str())){
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+        Some(index) => Err(format!("{}缺少参数", args[index])),
+        None => Ok(None)
+    }
+}
+
+//--script指定的文字
+fn take_script(args:&mut Vec<String>) -> Result<Option<Script>, String>{
+    match take_option(args, &["--script"])?.as_deref(){
+        None | Some("auto") => Ok(None),
+        Some(id) => Script::from_id(id).map(Some).ok_or_else(|| format!("未知的文字:{}", id))
+    }
+}
+
+fn data_script(list:&[(char, Strokes)], fixed: Option<Script>) -> DataScript{
+    let chars: Vec<char> = list.iter().map(|(ch, _)| *ch).collect();
+    DataScript::detect(&chars, fixed)
+}
+
+fn cmd_validate(mut args: Vec<String>) -> Result<i32, String>{
+    let fixed = take_script(&mut args)?;
+    if args.len() != 1{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let script = data_script(&list, fixed);
+    let mut problems = validate(&list);
+    //按字所属文字的规则检查
+    for (ch, strokes) in &list{
+        problems.extend(rules(script.of(*ch), strokes).into_iter().map(|problem| (*ch, problem)));
+    }
+    for (ch, problem) in &problems{
+        println!("{}\t{}", ch, problem);
+    }
+    eprintln!("共{}个字, {}个问题", list.len(), problems.len());
+    Ok(if problems.is_empty() { 0 } else { 1 })
+}
+
+fn cmd_convert(mut args: Vec<String>) -> Result<i32, String>{
+    let format = take_option(&mut args, &["--format", "-f"])?;
+    if args.len() != 2{
+        return Err(USAGE.to_string());
+    }
+    let (input, output) = (&args[0], &args[1]);
+    let format = format.unwrap_or_else(|| {
+        if is_json(output){
+            "json".to_string()
+        }else if output.to_lowercase().ends_with(".svg"){
+            "svg".to_string()
+        }else{
+            "vec".to_string()
+        }
+    });
+    let list = load(input)?;
+    //bincode格式保留审核信息和异体字关系
+    let (reviews, variants) = load_extras(input, &list)?;
+    match format.as_str(){
+        "vec" | "map" => {
+            let mut data = if format == "vec" { write_list(&list) } else { write_map(&list) };
+            append_reviews(&mut data, &reviews);
+            append_variants(&mut data, &variants);
+            write(output, &data)?;
+        }
+        "json" => write(output, serde_json::to_string(&list).unwrap().as_bytes())?,
+        "svg" => {
+            fs::create_dir_all(output).map_err(|err| format!("{}: {}", output, err))?;
+            for (ch, strokes) in &list{
+                let path = Path::new(output).join(format!("{}.svg", ch));
+                fs::write(&path, char_svg(strokes, SVG_SIZE)).map_err(|err| format!("{}: {}", path.display(), err))?;
+            }
+        }
+        _ => return Err(format!("不支持的格式:{}", format))
+    }
+    eprintln!("已转换{}个字", list.len());
+    Ok(0)
+}
+
+fn cmd_stats(args: Vec<String>) -> Result<i32, String>{
+    if args.len() != 1{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let strokes: usize = list.iter().map(|(_, strokes)| strokes.len()).sum();
+    let points: usize = list.iter().flat_map(|(_, strokes)| strokes.iter()).map(|stroke| stroke.len()).sum();
+    println!("字数\t{}", list.len());
+    println!("笔画数\t{}", strokes);
+    println!("点数\t{}", points);
+    if !list.is_empty(){
+        println!("平均笔画数\t{:.2}", strokes as f64 / list.len() as f64);
+    }
+    if strokes > 0{
+        println!("每笔平均点数\t{:.2}", points as f64 / strokes as f64);
+    }
+    if let Some((ch, max)) = list.iter().max_by_key(|(_, strokes)| strokes.len()){
+        println!("笔画最多\t{}({}笔)", ch, max.len());
+    }
+    //笔画数分布
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    for (_, strokes) in &list{
+        *counts.entry(strokes.len()).or_insert(0) += 1;
+    }
+    for (count, chars) in counts{
+        println!("{}笔\t{}", count, chars);
+    }
+    Ok(0)
+}
+
+fn cmd_render(mut args: Vec<String>) -> Result<i32, String>{
+    let output = take_option(&mut args, &["--output", "-o"])?.ok_or_else(|| USAGE.to_string())?;
+    let size = match take_option(&mut args, &["--size", "-s"])?{
+        Some(value) => value.parse().map_err(|_| format!("大小格式错误:{}", value))?,
+        None => SVG_SIZE
+    };
+    if args.len() < 2{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let map: HashMap<char, &Strokes> = list.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let mut chars = vec![];
+    for ch in args[1..].iter().flat_map(|arg| arg.chars()){
+        match map.get(&ch){
+            Some(strokes) => chars.push((ch, *strokes)),
+            None => return Err(format!("没有这个字:{}", ch))
+        }
+    }
+    write(&output, grid_svg(&chars, size, RENDER_COLUMNS).as_bytes())?;
+    Ok(0)
+}
+
+fn cmd_query(mut args: Vec<String>) -> Result<i32, String>{
+    let stroke_count = match take_option(&mut args, &["--strokes", "-n"])?{
+        Some(value) => Some(value.parse::<usize>().map_err(|_| format!("笔画数格式错误:{}", value))?),
+        None => None
+    };
+    if args.is_empty(){
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    //按笔画数查找字
+    if let Some(count) = stroke_count{
+        let chars: String = list.iter().filter(|(_, strokes)| strokes.len() == count).map(|(ch, _)| *ch).collect();
+        println!("{}", chars);
+        return Ok(if chars.is_empty() { 1 } else { 0 });
+    }
+    //输出每个字的笔画数据
+    let map: HashMap<char, &Strokes> = list.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let mut missing = false;
+    for ch in args[1..].iter().flat_map(|arg| arg.chars()){
+        match map.get(&ch){
+            Some(strokes) => {
+                println!("{}\t{}笔", ch, strokes.len());
+                for (i, stroke) in strokes.iter().enumerate(){
+                    let points: Vec<String> = stroke.iter().map(|(x, y)| format!("({},{})", x, y)).collect();
+                    println!("\t{}:\t{}", i + 1, points.join(" "));
+                }
+            }
+            None => {
+                println!("{}\t不存在", ch);
+                missing = true;
+            }
+        }
+    }
+    Ok(if missing { 1 } else { 0 })
+}
+
+fn cmd_coverage(mut args: Vec<String>) -> Result<i32, String>{
+    let missing = take_option(&mut args, &["--missing", "-m"])?;
+    let fixed = take_script(&mut args)?;
+    if args.len() != 2{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let script = data_script(&list, fixed);
+    let (reviews, _) = load_extras(&args[0], &list)?;
+    let text = fs::read_to_string(&args[1]).map_err(|err| format!("{}: {}", args[1], err))?;
+    let strokes: HashMap<char, Strokes> = list.into_iter().collect();
+    let report = Coverage::new(&text, &strokes, &reviews, &script);
+    for (ch, count) in &report.missing{
+        println!("缺少\t{}\t{}", ch, count);
+    }
+    for (ch, count) in &report.lint{
+        println!("有问题\t{}\t{}", ch, count);
+    }
+    if let Some(path) = missing{
+        write(&path, format_list(&report.missing).as_bytes())?;
+    }
+    eprintln!("{}", report.summary());
+    Ok(if report.missing.is_empty() { 0 } else { 1 })
+}
+
+//列出数据中的字缺少的简体字或繁体字
+fn cmd_variants(args: Vec<String>) -> Result<i32, String>{
+    if args.len() != 2{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let text = fs::read_to_string(&args[1]).map_err(|err| format!("{}: {}", args[1], err))?;
+    //数据中保存的关系和Unihan数据库中的关系
+    let (_, mut variants) = load_extras(&args[0], &list)?;
+    variants.add_unihan(&text);
+    let map: HashMap<char, &Strokes> = list.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let mut missing = 0;
+    for (ch, _) in &list{
+        for (variant, kind) in variants.get(*ch){
+            if matches!(kind, Kind::Simplified | Kind::Traditional) && !map.contains_key(&variant){
+                println!("{}\t{}\t{}", ch, kind.name(), variant);
+                missing += 1;
+            }
+        }
+    }
+    eprintln!("缺少{}个简繁体对应的字", missing);
+    Ok(if missing == 0 { 0 } else { 1 })
+}
+
+fn format_candidates(candidates:&[Candidate]) -> String{
+    let list: Vec<String> = candidates.iter().map(|candidate| format!("{}({:.3})", candidate.ch, candidate.score)).collect();
+    list.join(" ")
+}
+
+//识别.json文件中的笔画([[[x, y], ...], ...]), 或者用数据中字的笔画查找最像的字(第一个候选不是这个字时可能标错了)
+fn cmd_recognize(mut args: Vec<String>) -> Result<i32, String>{
+    let top = match take_option(&mut args, &["--top", "-n"])?{
+        Some(value) => value.parse().map_err(|_| format!("候选数格式错误:{}", value))?,
+        None => DEFAULT_TOP
+    };
+    if args.len() < 2{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let recognizer = Recognizer::new(list.iter().map(|(ch, strokes)| (*ch, strokes)));
+    let map: HashMap<char, &Strokes> = list.iter().map(|(ch, strokes)| (*ch, strokes)).collect();
+    let mut suspicious = false;
+    for arg in &args[1..]{
+        if is_json(arg){
+            let data = fs::read(arg).map_err(|err| format!("{}: {}", arg, err))?;
+            let strokes: Strokes = serde_json::from_slice(&data).map_err(|err| format!("{}: JSON格式错误:{}", arg, err))?;
+            println!("{}\t{}", arg, format_candidates(&recognizer.recognize(&strokes, top)));
+            continue;
+        }
+        for ch in arg.chars(){
+            let strokes = map.get(&ch).ok_or_else(|| format!("没有这个字:{}", ch))?;
+            let candidates = recognizer.recognize(strokes, top);
+            let mark = if candidates.first().map(|candidate| candidate.ch) != Some(ch){
+                suspicious = true;
+                "\t可能标错"
+            }else{
+                ""
+            };
+            println!("{}\t{}{}", ch, format_candidates(&candidates), mark);
+        }
+    }
+    Ok(if suspicious { 1 } else { 0 })
+}
+
+//列出笔画相同或者几乎相同的字
+fn cmd_duplicates(mut args: Vec<String>) -> Result<i32, String>{
+    let threshold = match take_option(&mut args, &["--threshold", "-t"])?{
+        Some(value) => value.parse().map_err(|_| format!("距离格式错误:{}", value))?,
+        None => NEAR_THRESHOLD
+    };
+    if args.len() != 1{
+        return Err(USAGE.to_string());
+    }
+    let list = load(&args[0])?;
+    let duplicates = duplicates(&list, threshold);
+    for duplicate in &duplicates{
+        let kind = if duplicate.identical { "完全相同" } else { "几乎相同" };
+        println!("{}\t{}\t{}\t{:.4}", duplicate.a, duplicate.b, kind, duplicate.score);
+    }
+    eprintln!("{}对字的笔画相同或几乎相同", duplicates.len());
+    Ok(if duplicates.is_empty() { 0 } else { 1 })
+}
+
+fn cmd_diff(mut args: Vec<String>) -> Result<i32, String>{
+    let tolerance = match take_option(&mut args, &["--tolerance", "-t"])?{
+        Some(value) => value.parse().map_err(|_| format!("容差格式错误:{}", value))?,
+        None => DEFAULT_TOLERANCE
+    };
+    if args.len() != 2{
+        return Err(USAGE.to_string());
+    }
+    let (old, new) = (load(&args[0])?, load(&args[1])?);
+    let order: Vec<char> = new.iter().map(|(ch, _)| *ch).collect();
+    let old: HashMap<char, Strokes> = old.into_iter().collect();
+    let new: HashMap<char, Strokes> = new.into_iter().collect();
+    let diffs = diff(&old, &new, &order, tolerance);
+    for (ch, diff) in &diffs{
+        println!("{}\t{}", ch, diff);
+    }
+    eprintln!("共{}个字有差异", diffs.len());
+    Ok(if diffs.is_empty() { 0 } else { 1 })
+}
+
+fn cmd_merge(mut args: Vec<String>) -> Result<i32, String>{
+    let output = take_option(&mut args, &["--output", "-o"])?.ok_or_else(|| USAGE.to_string())?;
+    if args.len() != 3{
+        return Err(USAGE.to_string());
+    }
+    let (base, ours, theirs) = (load(&args[0])?, load(&args[1])?, load(&args[2])?);
+    let merged = merge(&base, &ours, &theirs);
+    //审核信息按字三方合并, 异体字关系取并集
+    let (base_reviews, _) = load_extras(&args[0], &base)?;
+    let (ours_reviews, mut variants) = load_extras(&args[1], &ours)?;
+    let (theirs_reviews, theirs_variants) = load_extras(&args[2], &theirs)?;
+    variants.merge(&theirs_variants);
+    let mut data = write_list(&merged.list);
+    append_reviews(&mut data, &merge_reviews(&base_reviews, &ours_reviews, &theirs_reviews));
+    append_variants(&mut data, &variants);
+    write(&output, &data)?;
+    for conflict in &merged.conflicts{
+        let count = |strokes:&Option<Strokes>| match strokes{
+            Some(strokes) => format!("{}笔", strokes.len()),
+            None => "不存在".to_string()
+        };
+        println!("冲突\t{}\t原始:{}\t我们:{}\t他们:{}", conflict.ch, count(&conflict.base), count(&conflict.ours), count(&conflict.theirs));
+    }
+    eprintln!("合并了{}个字, {}个冲突(保留我们的版本)", merged.list.len(), merged.conflicts.len());
+    Ok(if merged.conflicts.is_empty() { 0 } else { 1 })
+}
+
+fn main(){
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty(){
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let command = args.remove(0);
+    let result = match command.as_str(){
+        "validate" => cmd_validate(args),
+        "convert" => cmd_convert(args),
+        "stats" => cmd_stats(args),
+        "diff" => cmd_diff(args),
+        "merge" => cmd_merge(args),
+        "render" => cmd_render(args),
+        "query" => cmd_query(args),
+        "coverage" => cmd_coverage(args),
+        "variants" => cmd_variants(args),
+        "recognize" => cmd_recognize(args),
+        "duplicates" => cmd_duplicates(args),
+        _ => Err(USAGE.to_string())
+    };
+    match result{
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    }
+}